@@ -0,0 +1,360 @@
+use super::converter::create_converter;
+use super::trace_reader::TraceEvent;
+use super::units::{converted_column, converted_values};
+use super::writer::EventWriter;
+use super::xml_parser::{Event, Paramter};
+use arrow::array::{
+    ArrayRef, BinaryBuilder, Float64Builder, StringBuilder, UInt16Builder, UInt32Builder,
+    UInt64Builder, UInt8Builder,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const BATCH_SIZE: usize = 65536;
+
+/// Arrow type of a parameter column, derived from the XML `type` and
+/// `numberofbytes`.
+pub fn column_type(parameter: &Paramter) -> DataType {
+    match parameter.param_type.to_lowercase().as_str() {
+        "uint" | "long" => match parameter.number_of_bytes {
+            1 => DataType::UInt8,
+            2 => DataType::UInt16,
+            3 | 4 => DataType::UInt32,
+            _ => DataType::UInt64,
+        },
+        "binary" => DataType::Binary,
+        _ => DataType::Utf8,
+    }
+}
+
+enum Column {
+    UInt8(UInt8Builder),
+    UInt16(UInt16Builder),
+    UInt32(UInt32Builder),
+    UInt64(UInt64Builder),
+    Float64(Float64Builder),
+    Binary(BinaryBuilder),
+    Utf8(StringBuilder),
+}
+
+impl Column {
+    fn new(data_type: &DataType) -> Column {
+        match data_type {
+            DataType::UInt8 => Column::UInt8(UInt8Builder::new()),
+            DataType::UInt16 => Column::UInt16(UInt16Builder::new()),
+            DataType::UInt32 => Column::UInt32(UInt32Builder::new()),
+            DataType::UInt64 => Column::UInt64(UInt64Builder::new()),
+            DataType::Float64 => Column::Float64(Float64Builder::new()),
+            DataType::Binary => Column::Binary(BinaryBuilder::new()),
+            _ => Column::Utf8(StringBuilder::new()),
+        }
+    }
+
+    /// Appends a decoded value, or null if it is missing or does not fit the
+    /// column type.
+    fn append(&mut self, value: Option<&str>) {
+        match self {
+            Column::UInt8(builder) => builder.append_option(value.and_then(|v| v.parse().ok())),
+            Column::UInt16(builder) => builder.append_option(value.and_then(|v| v.parse().ok())),
+            Column::UInt32(builder) => builder.append_option(value.and_then(|v| v.parse().ok())),
+            Column::UInt64(builder) => builder.append_option(value.and_then(|v| v.parse().ok())),
+            Column::Float64(builder) => builder.append_option(value.and_then(|v| v.parse().ok())),
+            Column::Binary(builder) => {
+                builder.append_option(value.and_then(|v| hex::decode(v).ok()))
+            }
+            Column::Utf8(builder) => builder.append_option(value),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Column::UInt8(builder) => Arc::new(builder.finish()),
+            Column::UInt16(builder) => Arc::new(builder.finish()),
+            Column::UInt32(builder) => Arc::new(builder.finish()),
+            Column::UInt64(builder) => Arc::new(builder.finish()),
+            Column::Float64(builder) => Arc::new(builder.finish()),
+            Column::Binary(builder) => Arc::new(builder.finish()),
+            Column::Utf8(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+/// Column builders for all events of one type.
+struct Table {
+    schema: SchemaRef,
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl Table {
+    fn new(event: &Event) -> Table {
+        let mut fields = vec![Field::new("timestamp", DataType::UInt64, false)];
+        for parameter in &event.parameters {
+            if create_converter(&parameter.param_type).is_some() {
+                fields.push(Field::new(&parameter.name, column_type(parameter), true));
+                if let Some(column) = converted_column(&parameter.name) {
+                    fields.push(Field::new(column, DataType::Float64, true));
+                }
+            }
+        }
+        let columns = fields.iter().map(|f| Column::new(f.data_type())).collect();
+        Table {
+            schema: Arc::new(Schema::new(fields)),
+            columns,
+            rows: 0,
+        }
+    }
+
+    fn append(&mut self, event: &TraceEvent) {
+        let values: HashMap<&str, &str> = event
+            .parameters
+            .iter()
+            .map(|p| (p.name.as_str(), p.value.as_str()))
+            .collect();
+        let converted = converted_values(event);
+        let timestamp = event.timestamp.to_string();
+
+        for (field, column) in self.schema.fields().iter().zip(self.columns.iter_mut()) {
+            if field.name() == "timestamp" {
+                column.append(Some(&timestamp));
+            } else if let Some(value) = converted.get(field.name()) {
+                column.append(Some(value));
+            } else {
+                column.append(values.get(field.name().as_str()).copied());
+            }
+        }
+        self.rows += 1;
+    }
+
+    fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let arrays = self.columns.iter_mut().map(|c| c.finish()).collect();
+        self.rows = 0;
+        RecordBatch::try_new(self.schema.clone(), arrays)
+    }
+}
+
+/// Collects decoded events into one Arrow table per event type, typed from
+/// the XML schema. Radio measurements are followed by a `<PARAM>_<unit>`
+/// column with their value in the physical unit.
+pub struct ArrowTables {
+    definitions: HashMap<String, Event>,
+    tables: HashMap<String, Table>,
+}
+
+impl ArrowTables {
+    pub fn new(events_definition: &HashMap<u16, Event>) -> ArrowTables {
+        let definitions = events_definition
+            .values()
+            .map(|event| (event.name.to_string(), event.clone()))
+            .collect();
+        ArrowTables {
+            definitions,
+            tables: HashMap::new(),
+        }
+    }
+
+    /// Adds an event and returns the number of rows buffered for its type.
+    pub fn append(&mut self, event: &TraceEvent) -> usize {
+        if !self.tables.contains_key(&event.name) {
+            let table = match self.definitions.get(&event.name) {
+                Some(definition) => Table::new(definition),
+                None => return 0,
+            };
+            self.tables.insert(event.name.to_string(), table);
+        }
+        let table = self.tables.get_mut(&event.name).unwrap();
+        table.append(event);
+        table.rows
+    }
+
+    pub fn finish_table(&mut self, name: &str) -> Result<Option<RecordBatch>, ArrowError> {
+        match self.tables.get_mut(name) {
+            Some(table) if table.rows > 0 => Ok(Some(table.finish()?)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn finish(&mut self) -> Result<Vec<(String, RecordBatch)>, ArrowError> {
+        let mut names: Vec<String> = self.tables.keys().cloned().collect();
+        names.sort();
+
+        let mut batches = Vec::new();
+        for name in names {
+            if let Some(batch) = self.finish_table(&name)? {
+                batches.push((name, batch));
+            }
+        }
+        Ok(batches)
+    }
+}
+
+/// Writes one `<EVENT_NAME>.parquet` file per event type into `directory`.
+pub struct ParquetWriter {
+    directory: PathBuf,
+    tables: ArrowTables,
+    writers: HashMap<String, ArrowWriter<File>>,
+}
+
+impl ParquetWriter {
+    pub fn new(directory: &str, events_definition: &HashMap<u16, Event>) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            tables: ArrowTables::new(events_definition),
+            writers: HashMap::new(),
+        })
+    }
+
+    fn write_batch(&mut self, name: &str, batch: RecordBatch) -> io::Result<()> {
+        if !self.writers.contains_key(name) {
+            let file = File::create(self.directory.join(format!("{}.parquet", name)))?;
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            let writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))
+                .map_err(io::Error::other)?;
+            self.writers.insert(name.to_string(), writer);
+        }
+        let writer = self.writers.get_mut(name).unwrap();
+        writer.write(&batch).map_err(io::Error::other)
+    }
+}
+
+impl EventWriter for ParquetWriter {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        if self.tables.append(event) >= BATCH_SIZE {
+            if let Some(batch) = self
+                .tables
+                .finish_table(&event.name)
+                .map_err(io::Error::other)?
+            {
+                self.write_batch(&event.name, batch)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        for (name, batch) in self.tables.finish().map_err(io::Error::other)? {
+            self.write_batch(&name, batch)?;
+        }
+        for (_, writer) in self.writers.drain() {
+            writer.close().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_reader::event;
+    use arrow::array::{Array, BinaryArray, Float64Array, UInt16Array};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn parameter(name: &str, param_type: &str, number_of_bytes: i64) -> Paramter {
+        Paramter {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            number_of_bytes,
+            enumeration: HashMap::new(),
+            related_name: "".to_string(),
+        }
+    }
+
+    fn events_definition() -> HashMap<u16, Event> {
+        let mut events_definition = HashMap::new();
+        events_definition.insert(
+            1,
+            Event {
+                name: "EVENT_A".to_string(),
+                id: 1,
+                elements: vec![],
+                parameters: vec![
+                    parameter("LENGTH", "UINT", 2),
+                    parameter("CONTENTS", "BINARY", -1),
+                    parameter("DIRECTION", "ENUM", 1),
+                    parameter("EVENT_PARAM_SERVING_RSRP", "UINT", 1),
+                ],
+            },
+        );
+        events_definition
+    }
+
+    #[test]
+    fn test_parquet_writer() {
+        let directory =
+            std::env::temp_dir().join(format!("ran_parser_parquet_{}", std::process::id()));
+        let mut writer =
+            ParquetWriter::new(directory.to_str().unwrap(), &events_definition()).unwrap();
+        writer
+            .write(&event("EVENT_A", 5, &[("LENGTH", "7")]))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let file = File::open(directory.join("EVENT_A.parquet")).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches[0].num_rows(), 1);
+        assert_eq!(batches[0].schema().field(1).name(), "LENGTH");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_arrow_tables() {
+        let mut tables = ArrowTables::new(&events_definition());
+        tables.append(&event(
+            "EVENT_A",
+            5,
+            &[
+                ("LENGTH", "2"),
+                ("CONTENTS", "0aff"),
+                ("DIRECTION", "EVENT_VALUE_SENT"),
+                ("EVENT_PARAM_SERVING_RSRP", "45"),
+            ],
+        ));
+        tables.append(&event("EVENT_A", 6, &[]));
+        let batches = tables.finish().unwrap();
+
+        let (name, batch) = &batches[0];
+        assert_eq!(name, "EVENT_A");
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::UInt16);
+        let length = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<UInt16Array>()
+            .unwrap();
+        assert_eq!(length.value(0), 2);
+        assert!(length.is_null(1));
+        let contents = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .unwrap();
+        assert_eq!(contents.value(0), &[0x0a, 0xff]);
+        assert_eq!(
+            batch.schema().field(5).name(),
+            "EVENT_PARAM_SERVING_RSRP_dBm"
+        );
+        let rsrp = batch
+            .column(5)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(rsrp.value(0), -95.0);
+        assert!(rsrp.is_null(1));
+    }
+}
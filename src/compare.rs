@@ -0,0 +1,357 @@
+use super::ctr_analyzer::{summarize_trace, Summary, SummaryScope, ValueCounts};
+use super::formatter::is_statistic;
+use super::kpi::{accessibility, retainability, KpiCounter};
+use super::session::build_sessions;
+use super::trace_reader::TraceEvent;
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// The summary and KPI totals of a set of trace files.
+#[derive(Debug, Default)]
+pub struct TraceSet {
+    pub summary: Summary,
+    pub kpis: IndexMap<String, KpiCounter>,
+}
+
+impl TraceSet {
+    /// Adds the events of one more file of the set.
+    pub fn add(&mut self, filename: &str, events: &[TraceEvent], scope: &SummaryScope) {
+        let mut summary = summarize_trace(events, scope);
+        summary.files.push(filename.to_string());
+        self.summary.merge(summary);
+
+        let sessions = build_sessions(events);
+        let cells = accessibility(events, &sessions, u64::MAX)
+            .into_iter()
+            .chain(retainability(events, &sessions, u64::MAX).cells);
+        for cell in cells {
+            for (kpi, counter) in &cell.kpis {
+                self.kpis.entry(kpi.to_string()).or_default().merge(counter);
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct EventDelta {
+    pub event: String,
+    pub before: u64,
+    pub after: u64,
+    pub delta: i64,
+}
+
+/// How far the values of a parameter moved: the Kolmogorov-Smirnov
+/// distance for the parameters summarised as statistics, the total
+/// variation distance for the others. Both range from 0, the same
+/// distribution, to 1.
+#[derive(Debug, Serialize)]
+pub struct ParameterShift {
+    pub event: String,
+    pub parameter: String,
+    pub measure: String,
+    pub distance: f64,
+    pub mean_before: Option<f64>,
+    pub mean_after: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KpiDelta {
+    pub kpi: String,
+    pub before: KpiCounter,
+    pub after: KpiCounter,
+}
+
+/// A cause value seen on only one side of the comparison.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CauseChange {
+    pub event: String,
+    pub parameter: String,
+    pub value: String,
+    pub change: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comparison {
+    pub before_files: Vec<String>,
+    pub after_files: Vec<String>,
+    pub events: Vec<EventDelta>,
+    pub parameters: Vec<ParameterShift>,
+    pub kpis: Vec<KpiDelta>,
+    pub causes: Vec<CauseChange>,
+}
+
+/// Compares two sets of traces, such as the ones before and after a
+/// parameter change. Parameter shifts are sorted by distance, largest
+/// first; parameters whose name contains `CAUSE` are checked for new and
+/// missing values.
+pub fn compare(
+    before: TraceSet,
+    after: TraceSet,
+    parameter_types: &HashMap<String, String>,
+) -> Comparison {
+    let empty = IndexMap::new();
+    let mut events = Vec::new();
+    let mut parameters = Vec::new();
+    let mut causes = Vec::new();
+
+    for event in union(before.summary.events.keys(), after.summary.events.keys()) {
+        let old = before.summary.events.get(&event);
+        let new = after.summary.events.get(&event);
+        let (old_count, new_count) = (
+            old.map_or(0, |event| event.count),
+            new.map_or(0, |event| event.count),
+        );
+        events.push(EventDelta {
+            event: event.to_string(),
+            before: old_count,
+            after: new_count,
+            delta: new_count as i64 - old_count as i64,
+        });
+
+        let old = old.map_or(&empty, |event| &event.parameters);
+        let new = new.map_or(&empty, |event| &event.parameters);
+        for parameter in union(old.keys(), new.keys()) {
+            let old_values = old.get(&parameter);
+            let new_values = new.get(&parameter);
+            let numeric = is_statistic(&parameter, parameter_types);
+            parameters.push(parameter_shift(
+                &event, &parameter, old_values, new_values, numeric,
+            ));
+            if parameter.contains("CAUSE") {
+                causes.extend(cause_changes(&event, &parameter, old_values, new_values));
+            }
+        }
+    }
+    parameters.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+
+    let mut kpis = Vec::new();
+    for kpi in union(before.kpis.keys(), after.kpis.keys()) {
+        kpis.push(KpiDelta {
+            before: before.kpis.get(&kpi).cloned().unwrap_or_default(),
+            after: after.kpis.get(&kpi).cloned().unwrap_or_default(),
+            kpi,
+        });
+    }
+
+    Comparison {
+        before_files: before.summary.files,
+        after_files: after.summary.files,
+        events,
+        parameters,
+        kpis,
+        causes,
+    }
+}
+
+/// Names from both sides, in the order they are first seen.
+fn union<'a>(
+    first: impl Iterator<Item = &'a String>,
+    second: impl Iterator<Item = &'a String>,
+) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    first
+        .chain(second)
+        .filter(|name| seen.insert(name.as_str()))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn parameter_shift(
+    event: &str,
+    parameter: &str,
+    before: Option<&ValueCounts>,
+    after: Option<&ValueCounts>,
+    numeric: bool,
+) -> ParameterShift {
+    let numbers = |values: Option<&ValueCounts>| -> Option<Vec<(f64, u64)>> {
+        values?
+            .iter()
+            .map(|(value, &count)| Some((value.parse().ok()?, count)))
+            .collect()
+    };
+    let mean = |values: &Option<Vec<(f64, u64)>>| {
+        let values = values.as_ref()?;
+        let count: u64 = values.iter().map(|(_, count)| count).sum();
+        let sum: f64 = values
+            .iter()
+            .map(|(value, count)| value * *count as f64)
+            .sum();
+        Some(sum / count as f64).filter(|_| count > 0)
+    };
+    let (old_numbers, new_numbers) = if numeric {
+        (numbers(before), numbers(after))
+    } else {
+        (None, None)
+    };
+    let (measure, distance) = match (before, after, &old_numbers, &new_numbers) {
+        (None, _, _, _) | (_, None, _, _) => ("none", 1.0),
+        (_, _, Some(old), Some(new)) => ("ks", kolmogorov_smirnov(old, new)),
+        (Some(old), Some(new), _, _) => ("tvd", total_variation(old, new)),
+    };
+    ParameterShift {
+        event: event.to_string(),
+        parameter: parameter.to_string(),
+        measure: measure.to_string(),
+        distance,
+        mean_before: mean(&old_numbers),
+        mean_after: mean(&new_numbers),
+    }
+}
+
+/// Largest difference between the cumulative distributions.
+fn kolmogorov_smirnov(before: &[(f64, u64)], after: &[(f64, u64)]) -> f64 {
+    let total = |values: &[(f64, u64)]| values.iter().map(|(_, count)| count).sum::<u64>() as f64;
+    let (before_total, after_total) = (total(before), total(after));
+    let mut points: Vec<(f64, f64)> = before
+        .iter()
+        .map(|&(value, count)| (value, count as f64 / before_total))
+        .chain(
+            after
+                .iter()
+                .map(|&(value, count)| (value, -(count as f64) / after_total)),
+        )
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut difference: f64 = 0.0;
+    let mut distance: f64 = 0.0;
+    for (index, (value, weight)) in points.iter().enumerate() {
+        difference += weight;
+        if points.get(index + 1).is_none_or(|next| next.0 != *value) {
+            distance = distance.max(difference.abs());
+        }
+    }
+    distance
+}
+
+/// Half the sum of the differences between the value frequencies.
+fn total_variation(before: &ValueCounts, after: &ValueCounts) -> f64 {
+    let total = |values: &ValueCounts| values.values().sum::<u64>() as f64;
+    let (before_total, after_total) = (total(before), total(after));
+    let frequency = |values: &ValueCounts, value: &str, total: f64| {
+        values.get(value).map_or(0.0, |&count| count as f64 / total)
+    };
+    union(before.keys(), after.keys())
+        .iter()
+        .map(|value| {
+            (frequency(before, value, before_total) - frequency(after, value, after_total)).abs()
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+fn cause_changes(
+    event: &str,
+    parameter: &str,
+    before: Option<&ValueCounts>,
+    after: Option<&ValueCounts>,
+) -> Vec<CauseChange> {
+    let empty = IndexMap::new();
+    let (before, after) = (before.unwrap_or(&empty), after.unwrap_or(&empty));
+    let change = |values: &ValueCounts, other: &ValueCounts, change: &str| {
+        values
+            .iter()
+            .filter(|(value, _)| !other.contains_key(*value))
+            .map(|(value, &count)| CauseChange {
+                event: event.to_string(),
+                parameter: parameter.to_string(),
+                value: value.to_string(),
+                change: change.to_string(),
+                count,
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut changes = change(after, before, "new");
+    changes.extend(change(before, after, "missing"));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::RAC_UE_REF;
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_compare() {
+        let release = |ueref: &str, cause: &str, volume: &str| {
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                0,
+                &[
+                    (RAC_UE_REF, ueref),
+                    ("EVENT_PARAM_3GPP_CAUSE", cause),
+                    ("EVENT_PARAM_VOLUME", volume),
+                ],
+            )
+        };
+        let scope = SummaryScope::default();
+        let mut before = TraceSet::default();
+        before.add(
+            "A.bin.gz",
+            &[release("1", "20", "1"), release("2", "20", "2")],
+            &scope,
+        );
+        let mut after = TraceSet::default();
+        after.add(
+            "B.bin.gz",
+            &[
+                release("1", "20", "3"),
+                release("2", "21", "4"),
+                release("3", "21", "5"),
+            ],
+            &scope,
+        );
+        let mut parameter_types = HashMap::new();
+        parameter_types.insert("EVENT_PARAM_VOLUME".to_string(), "UINT".to_string());
+
+        let comparison = compare(before, after, &parameter_types);
+        assert_eq!(
+            comparison.events,
+            vec![EventDelta {
+                event: "INTERNAL_PROC_UE_CTXT_RELEASE".to_string(),
+                before: 2,
+                after: 3,
+                delta: 1,
+            }]
+        );
+        let volume = &comparison.parameters[0];
+        assert_eq!(
+            (volume.parameter.as_str(), volume.measure.as_str()),
+            ("EVENT_PARAM_VOLUME", "ks")
+        );
+        assert_eq!(volume.distance, 1.0);
+        assert_eq!(
+            (volume.mean_before, volume.mean_after),
+            (Some(1.5), Some(4.0))
+        );
+        let cause = &comparison.parameters[1];
+        assert_eq!(cause.measure, "tvd");
+        assert!((cause.distance - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(comparison.causes.len(), 1);
+        assert_eq!(
+            (
+                comparison.causes[0].value.as_str(),
+                comparison.causes[0].change.as_str()
+            ),
+            ("21", "new")
+        );
+        assert_eq!(comparison.kpis[0].kpi, "ue_context_release");
+        assert_eq!(
+            (
+                comparison.kpis[0].before.attempts,
+                comparison.kpis[0].after.attempts
+            ),
+            (2, 3)
+        );
+    }
+
+    #[test]
+    fn test_kolmogorov_smirnov() {
+        let same = [(1.0, 1), (2.0, 1)];
+        assert_eq!(kolmogorov_smirnov(&same, &same), 0.0);
+        assert_eq!(kolmogorov_smirnov(&[(1.0, 1), (2.0, 1)], &[(2.0, 1)]), 0.5);
+    }
+}
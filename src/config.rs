@@ -1,3 +1,14 @@
+use super::ctr_analyzer::SummaryScope;
+use super::formatter::DEFAULT_TOP;
+use log::LevelFilter;
+
+const OUTPUT_OPTIONS: &str =
+    "did not specify result output, options 'table', 'summary', 'row', 'csv', 'csv-wide', 'jsonl', 'parquet', 'sqlite', 'pcap', 'sessions', 'ladder', 'ladder-svg', 'ladder-plantuml', 'ladder-mermaid', 'accessibility', 'retainability', 'handover', 'measurements', 'distance', 'distance-csv', 'neighbours', 'series' and 'series-csv', or 'merge' and 'compare' in place of the file";
+
+pub(crate) const DEFAULT_BIN_MS: u64 = 15 * 60 * 1000;
+pub(crate) const DEFAULT_PING_PONG_MS: u64 = 5 * 1000;
+pub(crate) const DEFAULT_DISTANCE_BIN_M: f64 = 500.0;
+pub(crate) const DEFAULT_RADIUS_M: f64 = 5000.0;
 
 pub struct Config {
     pub xml: String,
@@ -5,31 +16,189 @@ pub struct Config {
     pub output: Processing,
     pub filter: String,
     pub ueref: String,
+    pub log_level: LevelFilter,
+    pub output_path: Option<String>,
+    pub bin_ms: u64,
+    pub ping_pong_ms: u64,
+    pub distance_bin_m: f64,
+    pub radius_m: f64,
+    pub top: usize,
+    pub summary_scope: SummaryScope,
 }
 
 pub enum Processing {
     Table,
     Summary,
     Row,
+    Schema(SchemaQuery),
+    SchemaDiff {
+        xml: String,
+        json: bool,
+    },
+    Csv(CsvLayout),
+    JsonLines,
+    Parquet,
+    Sqlite,
+    Pcap,
+    Sessions,
+    Ladder(LadderFormat),
+    Accessibility,
+    Retainability,
+    Handover,
+    Measurements,
+    Distance {
+        csv: bool,
+    },
+    Neighbours,
+    Series {
+        csv: bool,
+    },
+    Merge(Vec<String>),
+    Compare {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+}
+
+pub enum CsvLayout {
+    Long,
+    Wide,
+}
+
+pub enum LadderFormat {
+    Text,
+    Svg,
+    PlantUml,
+    Mermaid,
+}
+
+pub enum SchemaQuery {
+    Events,
+    Event(String),
+    Search(String),
+    Validate,
 }
 
 impl Config {
-    pub fn new(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
-        args.next();
+    pub fn new(args: impl Iterator<Item = String>) -> Result<Config, String> {
+        let mut log_level = LevelFilter::Warn;
+        let mut output_path: Option<String> = None;
+        let mut bin_ms = DEFAULT_BIN_MS;
+        let mut ping_pong_ms = DEFAULT_PING_PONG_MS;
+        let mut distance_bin_m = DEFAULT_DISTANCE_BIN_M;
+        let mut radius_m = DEFAULT_RADIUS_M;
+        let mut top = DEFAULT_TOP;
+        let mut summary_scope = SummaryScope::default();
+        let mut include_events: Option<Vec<String>> = None;
+        let mut exclude_events: Vec<String> = Vec::new();
+        let mut exclude_parameters: Vec<String> = Vec::new();
+        let mut positional: Vec<String> = Vec::new();
+
+        let mut args = args.skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-v" | "--verbose" => log_level = LevelFilter::Info,
+                "-vv" => log_level = LevelFilter::Debug,
+                "-q" | "--quiet" => log_level = LevelFilter::Error,
+                "-o" | "--output" => match args.next() {
+                    Some(path) => output_path = Some(path),
+                    None => return Err("did not get output path after '-o'".to_string()),
+                },
+                "--bin" => match args.next().as_deref().and_then(parse_duration) {
+                    Some(duration) => bin_ms = duration,
+                    None => {
+                        return Err(
+                            "did not get a bin size like '1s', '1m' or '15m' after '--bin'"
+                                .to_string(),
+                        )
+                    }
+                },
+                "--ping-pong" => match args.next().as_deref().and_then(parse_duration) {
+                    Some(duration) => ping_pong_ms = duration,
+                    None => {
+                        return Err(
+                            "did not get a duration like '5s' after '--ping-pong'".to_string(),
+                        )
+                    }
+                },
+                "--distance-bin" => match args.next().as_deref().and_then(parse_distance) {
+                    Some(distance) => distance_bin_m = distance,
+                    None => {
+                        return Err(
+                            "did not get a distance like '500m' after '--distance-bin'".to_string(),
+                        )
+                    }
+                },
+                "--radius" => match args.next().as_deref().and_then(parse_distance) {
+                    Some(distance) => radius_m = distance,
+                    None => {
+                        return Err("did not get a distance like '5km' after '--radius'".to_string())
+                    }
+                },
+                "--top" => match args.next().and_then(|count| count.parse().ok()) {
+                    Some(count) => top = count,
+                    None => return Err("did not get a number of values after '--top'".to_string()),
+                },
+                "--summary-config" => match args.next() {
+                    Some(path) => summary_scope = SummaryScope::from_file(&path)?,
+                    None => {
+                        return Err(
+                            "did not get a summary config file after '--summary-config'"
+                                .to_string(),
+                        )
+                    }
+                },
+                "--include-events" => match args.next() {
+                    Some(patterns) => include_events = Some(split_patterns(&patterns)),
+                    None => {
+                        return Err(
+                            "did not get event patterns after '--include-events'".to_string(),
+                        )
+                    }
+                },
+                "--exclude-events" => match args.next() {
+                    Some(patterns) => exclude_events.extend(split_patterns(&patterns)),
+                    None => {
+                        return Err(
+                            "did not get event patterns after '--exclude-events'".to_string(),
+                        )
+                    }
+                },
+                "--exclude-parameters" => match args.next() {
+                    Some(patterns) => exclude_parameters.extend(split_patterns(&patterns)),
+                    None => {
+                        return Err(
+                            "did not get parameter patterns after '--exclude-parameters'"
+                                .to_string(),
+                        )
+                    }
+                },
+                _ if arg.starts_with('-') && arg.len() > 1 => return Err(
+                    "unknown option, options '-v', '-vv', '-q', '-o', '--bin', '--ping-pong', '--distance-bin', '--radius', '--top', '--summary-config', '--include-events', '--exclude-events' and '--exclude-parameters'".to_string(),
+                ),
+                _ => positional.push(arg),
+            }
+        }
+
+        // Options on the command line refine the profile from the file:
+        // included events replace its list, exclusions add to it.
+        if let Some(include_events) = include_events {
+            summary_scope.include_events = include_events;
+        }
+        summary_scope.exclude_events.extend(exclude_events);
+        summary_scope.exclude_parameters.extend(exclude_parameters);
+
+        let mut args = positional.into_iter();
         let xml = match args.next() {
             Some(arg) => arg,
-            None => return Err("did not get xml file"),
+            None => return Err("did not get xml file".to_string()),
         };
-        let filename = match args.next() {
-            Some(arg) => arg,
-            None => return Err("did not get a file to parse"),
-        };
-        let output = match args.next() {
-            Some(arg) if arg == "table" => Processing::Table,
-            Some(arg) if arg == "summary" => Processing::Summary,
-            Some(arg) if arg == "row" => Processing::Row,
-            Some(_) => return Err("did not specify result output, options 'table' and 'summary'"),
-            None => return Err("did not specify result output, options 'table' and 'summary'"),
+        let (filename, output) = match args.next() {
+            Some(arg) if arg == "schema" => ("".to_string(), parse_schema_query(&mut args)?),
+            Some(arg) if arg == "merge" => ("".to_string(), parse_merge(&mut args)?),
+            Some(arg) if arg == "compare" => ("".to_string(), parse_compare(&mut args)?),
+            Some(arg) => (arg, parse_output(args.next(), output_path.is_some())?),
+            None => return Err("did not get a file to parse".to_string()),
         };
         let filter = match args.next() {
             Some(arg) => arg,
@@ -45,6 +214,143 @@ impl Config {
             output,
             filter,
             ueref,
+            log_level,
+            output_path,
+            bin_ms,
+            ping_pong_ms,
+            distance_bin_m,
+            radius_m,
+            top,
+            summary_scope,
         })
     }
 }
+
+/// Output of a trace file, some of which need an output path.
+fn parse_output(arg: Option<String>, has_output_path: bool) -> Result<Processing, &'static str> {
+    let output = match arg {
+        Some(arg) if arg == "table" => Processing::Table,
+        Some(arg) if arg == "summary" => Processing::Summary,
+        Some(arg) if arg == "row" => Processing::Row,
+        Some(arg) if arg == "csv" => Processing::Csv(CsvLayout::Long),
+        Some(arg) if arg == "jsonl" => Processing::JsonLines,
+        Some(arg) if arg == "pcap" => Processing::Pcap,
+        Some(arg) if arg == "sessions" => Processing::Sessions,
+        Some(arg) if arg == "accessibility" => Processing::Accessibility,
+        Some(arg) if arg == "retainability" => Processing::Retainability,
+        Some(arg) if arg == "handover" => Processing::Handover,
+        Some(arg) if arg == "measurements" => Processing::Measurements,
+        Some(arg) if arg == "distance" => Processing::Distance { csv: false },
+        Some(arg) if arg == "distance-csv" => Processing::Distance { csv: true },
+        Some(arg) if arg == "neighbours" => Processing::Neighbours,
+        Some(arg) if arg == "series" => Processing::Series { csv: false },
+        Some(arg) if arg == "series-csv" => Processing::Series { csv: true },
+        Some(arg) if arg == "ladder" => Processing::Ladder(LadderFormat::Text),
+        Some(arg) if arg == "ladder-svg" => Processing::Ladder(LadderFormat::Svg),
+        Some(arg) if arg == "ladder-plantuml" => Processing::Ladder(LadderFormat::PlantUml),
+        Some(arg) if arg == "ladder-mermaid" => Processing::Ladder(LadderFormat::Mermaid),
+        Some(arg) if arg == "parquet" => {
+            if !has_output_path {
+                return Err("parquet writes one file per event, specify a directory with '-o'");
+            }
+            Processing::Parquet
+        }
+        Some(arg) if arg == "sqlite" => {
+            if !has_output_path {
+                return Err("sqlite needs a database file, specify it with '-o'");
+            }
+            Processing::Sqlite
+        }
+        Some(arg) if arg == "csv-wide" => {
+            if !has_output_path {
+                return Err("csv-wide writes one file per event, specify a directory with '-o'");
+            }
+            Processing::Csv(CsvLayout::Wide)
+        }
+        Some(_) => return Err(OUTPUT_OPTIONS),
+        None => return Err(OUTPUT_OPTIONS),
+    };
+    Ok(output)
+}
+
+fn parse_schema_query(mut args: impl Iterator<Item = String>) -> Result<Processing, &'static str> {
+    match args.next() {
+        None => Ok(Processing::Schema(SchemaQuery::Events)),
+        Some(arg) if arg == "events" => Ok(Processing::Schema(SchemaQuery::Events)),
+        Some(arg) if arg == "event" => match args.next() {
+            Some(key) => Ok(Processing::Schema(SchemaQuery::Event(key))),
+            None => Err("did not get event id or name"),
+        },
+        Some(arg) if arg == "search" => match args.next() {
+            Some(pattern) => Ok(Processing::Schema(SchemaQuery::Search(pattern))),
+            None => Err("did not get parameter name to search"),
+        },
+        Some(arg) if arg == "validate" => Ok(Processing::Schema(SchemaQuery::Validate)),
+        Some(arg) if arg == "diff" => match args.next() {
+            Some(xml) => Ok(Processing::SchemaDiff {
+                xml,
+                json: matches!(args.next(), Some(format) if format == "json"),
+            }),
+            None => Err("did not get xml file to compare with"),
+        },
+        Some(_) => {
+            Err("unknown schema query, options 'events', 'event', 'search', 'validate' and 'diff'")
+        }
+    }
+}
+
+fn parse_merge(args: impl Iterator<Item = String>) -> Result<Processing, &'static str> {
+    let summaries: Vec<String> = args.collect();
+    if summaries.is_empty() {
+        return Err("did not get summary files to merge");
+    }
+    Ok(Processing::Merge(summaries))
+}
+
+/// Two sets of trace files separated by `vs`.
+fn parse_compare(args: impl Iterator<Item = String>) -> Result<Processing, &'static str> {
+    let files: Vec<String> = args.collect();
+    match files.iter().position(|file| file == "vs") {
+        Some(split) if split > 0 && split + 1 < files.len() => Ok(Processing::Compare {
+            before: files[..split].to_vec(),
+            after: files[split + 1..].to_vec(),
+        }),
+        _ => Err("did not get trace files to compare, like 'a.bin.gz vs b.bin.gz'"),
+    }
+}
+
+/// Duration like `500ms`, `1s`, `1m` or `15m`, in milliseconds.
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let split = text.find(|c: char| !c.is_ascii_digit())?;
+    let value: u64 = text[..split].parse().ok()?;
+    let unit = match &text[split..] {
+        "ms" => 1,
+        "s" => 1000,
+        "m" | "min" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return None,
+    };
+    value.checked_mul(unit).filter(|&duration| duration > 0)
+}
+
+/// Distance like `500`, `500m` or `5km`, in metres.
+fn parse_distance(text: &str) -> Option<f64> {
+    let (value, scale) = match text.strip_suffix("km") {
+        Some(value) => (value, 1000.0),
+        None => (text.strip_suffix('m').unwrap_or(text), 1.0),
+    };
+    value
+        .parse::<f64>()
+        .ok()
+        .map(|value| value * scale)
+        .filter(|&distance| distance > 0.0)
+}
+
+/// Comma separated name patterns.
+fn split_patterns(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(str::to_string)
+        .collect()
+}
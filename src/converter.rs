@@ -16,6 +16,11 @@ pub fn create_converter(name: &str) -> Option<Box<dyn Converter>> {
     converter
 }
 
+/// Whether values of this parameter type are decoded as integers.
+pub fn is_numeric(name: &str) -> bool {
+    matches!(name.to_lowercase().as_str(), "uint" | "long")
+}
+
 struct IntConverter;
 impl Converter for IntConverter {
     fn convert(&self, record: &[u8], parameter: &Paramter) -> TraceParameter {
@@ -1,5 +1,7 @@
 use super::trace_reader::{TraceEvent, TraceParameter};
 use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 
 const EXCLUDE_FROM_SUMMARY: [&str; 21] = [
     "EVENT_PARAM_TIMESTAMP_HOUR",
@@ -25,36 +27,161 @@ const EXCLUDE_FROM_SUMMARY: [&str; 21] = [
     "EVENT_PARAM_TIMESTAMP_STOP_MILLISEC",
 ];
 
-type SumEvent = IndexMap<String, IndexMap<String, u32>>;
-type Summary = IndexMap<String, SumEvent>;
+/// How often each value of a parameter occurred.
+pub type ValueCounts = IndexMap<String, u64>;
 
-pub fn summarize_trace(events: &[TraceEvent]) -> Summary {
-    let mut summary: Summary = IndexMap::new();
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SumEvent {
+    pub count: u64,
+    pub parameters: IndexMap<String, ValueCounts>,
+}
+
+/// Event and parameter value counts of one or more trace files. Summaries
+/// of separate files or runs merge into the summary of all of them, and the
+/// statistics derived from the value counts stay exact.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub files: Vec<String>,
+    pub events: IndexMap<String, SumEvent>,
+}
+
+impl Summary {
+    /// Adds the counts of `other`. Merging is associative and, apart from
+    /// the order events and values are listed in, commutative.
+    pub fn merge(&mut self, other: Summary) {
+        self.files.extend(other.files);
+        for (name, other_event) in other.events {
+            let event = self.events.entry(name).or_default();
+            event.count += other_event.count;
+            for (parameter, values) in other_event.parameters {
+                let counts = event.parameters.entry(parameter).or_default();
+                for (value, count) in values {
+                    *counts.entry(value).or_default() += count;
+                }
+            }
+        }
+    }
+
+    /// Reads a summary written as JSON.
+    pub fn from_file(path: &str) -> Result<Summary, String> {
+        read_json(path)
+    }
+}
+
+/// Which events and parameters the summary covers, as name patterns where
+/// `*` matches any run of characters. The default profile summarises the
+/// `INTERNAL` events without the timestamp, identifier and L3 message
+/// parameters.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SummaryScope {
+    pub include_events: Vec<String>,
+    pub exclude_events: Vec<String>,
+    pub exclude_parameters: Vec<String>,
+}
+
+impl Default for SummaryScope {
+    fn default() -> Self {
+        SummaryScope {
+            include_events: vec!["INTERNAL*".to_string()],
+            exclude_events: Vec::new(),
+            exclude_parameters: EXCLUDE_FROM_SUMMARY
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl SummaryScope {
+    /// Reads a scope from a JSON file with any of the `include_events`,
+    /// `exclude_events` and `exclude_parameters` lists. Missing lists keep
+    /// the default profile.
+    pub fn from_file(path: &str) -> Result<SummaryScope, String> {
+        read_json(path)
+    }
+
+    pub fn includes_event(&self, name: &str) -> bool {
+        matches_any(&self.include_events, name) && !matches_any(&self.exclude_events, name)
+    }
+
+    pub fn includes_parameter(&self, name: &str) -> bool {
+        !matches_any(&self.exclude_parameters, name)
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path, err))?;
+    serde_json::from_str(&text).map_err(|err| format!("could not parse {}: {}", path, err))
+}
+
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| matches_pattern(pattern, name))
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any characters.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some((last, middle)) => (*last, middle),
+        None => return rest.is_empty(),
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+pub fn summarize_trace(events: &[TraceEvent], scope: &SummaryScope) -> Summary {
+    let mut summary = Summary::default();
 
     for event in events {
         let key = &event.name;
 
-        match get_sum_event(&mut summary, key) {
-            Some(sum_event) => sum_parameters(sum_event, &event.parameters),
+        match get_sum_event(&mut summary, key, scope) {
+            Some(sum_event) => sum_parameters(sum_event, &event.parameters, scope),
             None => {}
         }
     }
     summary
 }
 
-fn get_sum_event<'a>(summary: &'a mut Summary, key: &str) -> Option<&'a mut SumEvent> {
-    if key.starts_with("INTERNAL") {
-        Some(summary.entry(key.to_string()).or_insert(IndexMap::new()))
+fn get_sum_event<'a>(
+    summary: &'a mut Summary,
+    key: &str,
+    scope: &SummaryScope,
+) -> Option<&'a mut SumEvent> {
+    if scope.includes_event(key) {
+        let sum_event = summary.events.entry(key.to_string()).or_default();
+        sum_event.count += 1;
+        Some(sum_event)
     } else {
         None
     }
 }
 
-fn sum_parameters(sum_event: &mut SumEvent, parameters: &Vec<TraceParameter>) {
+fn sum_parameters(
+    sum_event: &mut SumEvent,
+    parameters: &Vec<TraceParameter>,
+    scope: &SummaryScope,
+) {
     for parameter in parameters {
         let parameter_name = &parameter.name;
-        if !EXCLUDE_FROM_SUMMARY.contains(&parameter_name.as_str()) {
+        if scope.includes_parameter(parameter_name) {
             let parameter_sum = sum_event
+                .parameters
                 .entry(parameter_name.to_string())
                 .or_insert(IndexMap::new());
 
@@ -68,6 +195,7 @@ fn sum_parameters(sum_event: &mut SumEvent, parameters: &Vec<TraceParameter>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::trace_reader::event;
 
     #[test]
     fn test_summarize_trace() {
@@ -87,14 +215,17 @@ mod tests {
         };
         let events = vec![event1];
 
-        let mut summary: Summary = IndexMap::new();
-        let mut event: SumEvent = IndexMap::new();
-        let mut value: IndexMap<String, u32> = IndexMap::new();
+        let mut summary = Summary::default();
+        let mut event = SumEvent {
+            count: 1,
+            ..Default::default()
+        };
+        let mut value: ValueCounts = IndexMap::new();
         value.insert("value1".to_string(), 2);
-        event.insert("param1".to_string(), value);
-        summary.insert("INTERNAL_event1".to_string(), event);
+        event.parameters.insert("param1".to_string(), value);
+        summary.events.insert("INTERNAL_event1".to_string(), event);
 
-        assert_eq!(summary, summarize_trace(&events));
+        assert_eq!(summary, summarize_trace(&events, &SummaryScope::default()));
     }
 
     #[test]
@@ -115,8 +246,58 @@ mod tests {
         };
         let events = vec![event1];
 
-        let summary: Summary = IndexMap::new();
+        let summary = Summary::default();
+
+        assert_eq!(summary, summarize_trace(&events, &SummaryScope::default()));
+    }
+
+    #[test]
+    fn test_merge_summaries() {
+        let event1 = |value: &str| event("INTERNAL_event1", 1, &[("param1", value)]);
+        let scope = SummaryScope::default();
+        let mut first = summarize_trace(&[event1("value1")], &scope);
+        first.files.push("A.bin.gz".to_string());
+        let second = summarize_trace(&[event1("value1"), event1("value2")], &scope);
+
+        first.merge(second);
+        assert_eq!(
+            first,
+            Summary {
+                files: vec!["A.bin.gz".to_string()],
+                ..summarize_trace(
+                    &[event1("value1"), event1("value1"), event1("value2")],
+                    &scope
+                )
+            }
+        );
+        let json = serde_json::to_string(&first).unwrap();
+        assert_eq!(serde_json::from_str::<Summary>(&json).unwrap(), first);
+    }
+
+    #[test]
+    fn test_summary_scope() {
+        let scope = SummaryScope {
+            include_events: vec!["INTERNAL_PROC_*".to_string(), "RRC_*".to_string()],
+            exclude_events: vec!["*_SETUP".to_string()],
+            exclude_parameters: vec!["EVENT_PARAM_*_ID".to_string()],
+        };
+        assert!(scope.includes_event("INTERNAL_PROC_UE_CTXT_RELEASE"));
+        assert!(scope.includes_event("RRC_MEASUREMENT_REPORT"));
+        assert!(!scope.includes_event("INTERNAL_PROC_RRC_CONN_SETUP"));
+        assert!(!scope.includes_event("INTERNAL_PER_UE_TRAFFIC_REP"));
+        assert!(!scope.includes_parameter("EVENT_PARAM_GLOBAL_CELL_ID"));
+        assert!(scope.includes_parameter("EVENT_PARAM_RAC_UE_REF"));
 
-        assert_eq!(summary, summarize_trace(&events));
+        let default = SummaryScope::default();
+        assert!(default.includes_event("INTERNAL_PROC_RRC_CONN_SETUP"));
+        assert!(!default.includes_event("RRC_MEASUREMENT_REPORT"));
+        assert!(!default.includes_parameter("EVENT_PARAM_TIMESTAMP_HOUR"));
+        assert_eq!(
+            serde_json::from_str::<SummaryScope>(r#"{"exclude_events": ["*_REP"]}"#).unwrap(),
+            SummaryScope {
+                exclude_events: vec!["*_REP".to_string()],
+                ..SummaryScope::default()
+            }
+        );
     }
 }
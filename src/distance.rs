@@ -0,0 +1,188 @@
+use super::kpi::event_cells;
+use super::session::build_sessions;
+use super::stats::{Distribution, Statistics};
+use super::trace_reader::TraceEvent;
+use super::units::{conversion, convert, TA_METRES};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Share of the samples beyond the expected radius from which a cell is
+/// reported as overshooting.
+const OVERSHOOT_SHARE: f64 = 0.1;
+
+/// Largest distance a timing advance can give, 1282 steps of 16 Ts. Larger
+/// values, such as an all-ones "not available" value, are dropped.
+const MAX_DISTANCE_METRES: f64 = 1282.0 * 16.0 * TA_METRES;
+
+/// Multiple of the expected radius the histogram bins cover, with a last
+/// bin for all farther samples.
+const HISTOGRAM_RADII: f64 = 4.0;
+
+/// Distance of the UEs from one cell, estimated from the timing advance.
+/// `counts[i]` is the number of samples from `i * bin_metres` up to the
+/// next bin, or without an upper limit for the last bin when `capped`.
+#[derive(Debug, Serialize)]
+pub struct DistanceHistogram {
+    pub cell: String,
+    pub bin_metres: f64,
+    pub counts: Vec<u64>,
+    pub capped: bool,
+    pub statistics: Statistics,
+    pub beyond_radius: u64,
+    pub overshooting: bool,
+}
+
+impl DistanceHistogram {
+    /// Samples beyond the expected radius, in percent.
+    pub fn beyond_radius_rate(&self) -> f64 {
+        100.0 * self.beyond_radius as f64 / self.statistics.count as f64
+    }
+}
+
+/// Per-cell histograms of the timing advance parameters converted to
+/// metres, up to a few times `radius_metres`. A cell is overshooting when
+/// more than a tenth of its samples are farther than `radius_metres`.
+pub fn distance_histograms(
+    events: &[TraceEvent],
+    bin_metres: f64,
+    radius_metres: f64,
+) -> Vec<DistanceHistogram> {
+    let cells = event_cells(events, &build_sessions(events));
+    let mut distributions: BTreeMap<&str, Distribution> = BTreeMap::new();
+    for (event, cell) in events.iter().zip(&cells) {
+        for parameter in &event.parameters {
+            if !is_timing_advance(&parameter.name) {
+                continue;
+            }
+            match convert(&parameter.name, &parameter.value) {
+                Some((metres, _)) if metres <= MAX_DISTANCE_METRES => {
+                    distributions.entry(cell).or_default().add(metres)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    distributions
+        .into_iter()
+        .filter_map(|(cell, distribution)| {
+            let statistics = distribution.statistics()?;
+            let overflow_bin = (HISTOGRAM_RADII * radius_metres / bin_metres).ceil() as usize;
+            let last_bin = ((statistics.max / bin_metres) as usize).min(overflow_bin);
+            let mut counts = vec![0; last_bin + 1];
+            for &metres in distribution.values() {
+                counts[((metres / bin_metres) as usize).min(last_bin)] += 1;
+            }
+            let beyond_radius = distribution
+                .values()
+                .iter()
+                .filter(|&&metres| metres > radius_metres)
+                .count() as u64;
+            Some(DistanceHistogram {
+                cell: cell.to_string(),
+                bin_metres,
+                counts,
+                capped: last_bin == overflow_bin,
+                overshooting: beyond_radius as f64 > OVERSHOOT_SHARE * statistics.count as f64,
+                statistics,
+                beyond_radius,
+            })
+        })
+        .collect()
+}
+
+fn is_timing_advance(name: &str) -> bool {
+    matches!(conversion(name), Some(conversion) if conversion.pattern == "TA")
+}
+
+/// Writes the histograms as `cell,from_m,to_m,count` rows, with an empty
+/// `to_m` for the last bin of a capped histogram.
+pub fn write_histograms_csv<W: Write>(
+    output: W,
+    histograms: &[DistanceHistogram],
+) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record(["cell", "from_m", "to_m", "count"])?;
+    for histogram in histograms {
+        for (bin, count) in histogram.counts.iter().enumerate() {
+            let to = if histogram.capped && bin + 1 == histogram.counts.len() {
+                "".to_string()
+            } else {
+                ((bin + 1) as f64 * histogram.bin_metres).to_string()
+            };
+            writer.write_record([
+                histogram.cell.to_string(),
+                (bin as f64 * histogram.bin_metres).to_string(),
+                to,
+                count.to_string(),
+            ])?;
+        }
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{GLOBAL_CELL_ID, RAC_UE_REF};
+    use crate::trace_reader::event;
+
+    fn traffic_report(ueref: &str, cell: &str, ta: &str) -> TraceEvent {
+        event(
+            "INTERNAL_PER_UE_TRAFFIC_REP",
+            0,
+            &[
+                (RAC_UE_REF, ueref),
+                (GLOBAL_CELL_ID, cell),
+                ("EVENT_ARRAY_TA", ta),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_distance_histograms() {
+        // 64 Ts is about 312 m, 256 Ts about 1249 m.
+        let events = vec![
+            traffic_report("1", "11", "64"),
+            traffic_report("2", "11", "64"),
+            traffic_report("3", "11", "256"),
+            traffic_report("4", "12", "64"),
+        ];
+        let histograms = distance_histograms(&events, 500.0, 1000.0);
+
+        assert_eq!(histograms.len(), 2);
+        assert_eq!(histograms[0].cell, "11");
+        assert_eq!(histograms[0].counts, vec![2, 0, 1]);
+        assert_eq!(histograms[0].beyond_radius, 1);
+        assert!(histograms[0].overshooting);
+        assert!(!histograms[1].overshooting);
+
+        let mut csv = Vec::new();
+        write_histograms_csv(&mut csv, &histograms[1..]).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "cell,from_m,to_m,count\n12,0,500,1\n"
+        );
+    }
+
+    #[test]
+    fn test_distance_histograms_out_of_range() {
+        // 1024 Ts is about 5 km, past the 4 km the bins cover.
+        let events = vec![
+            traffic_report("1", "11", "64"),
+            traffic_report("2", "11", "1024"),
+            traffic_report("3", "11", "4294967295"),
+        ];
+        let histograms = distance_histograms(&events, 500.0, 1000.0);
+
+        let histogram = &histograms[0];
+        assert_eq!(histogram.statistics.count, 2);
+        assert_eq!(histogram.counts, vec![1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert!(histogram.capped);
+
+        let mut csv = Vec::new();
+        write_histograms_csv(&mut csv, &histograms).unwrap();
+        assert!(String::from_utf8(csv).unwrap().ends_with("11,4000,,1\n"));
+    }
+}
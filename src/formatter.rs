@@ -1,33 +1,107 @@
+use super::converter::is_numeric;
+use super::ctr_analyzer::{SumEvent, Summary, ValueCounts};
+use super::session::{GLOBAL_CELL_ID, IDENTIFIERS};
+use super::stats::{counted_statistics, Statistics};
+use super::units::{convert, converted_column};
 use indexmap::IndexMap;
 use std::cmp::min;
+use std::collections::HashMap;
 
-type SumEvent = IndexMap<String, IndexMap<String, u32>>;
-type Summary = IndexMap<String, SumEvent>;
+pub const DEFAULT_TOP: usize = 5;
 
-pub fn format_summary(summary: Summary) -> IndexMap<String, String> {
+/// Formats the summary per event. Parameters declared as integers in
+/// `parameter_types` are shown as statistics of their values, followed by
+/// the statistics in the physical unit for the radio measurements, the
+/// others, and the UE and cell identifiers, as their `top` most frequent
+/// values.
+pub fn format_summary(
+    summary: Summary,
+    parameter_types: &HashMap<String, String>,
+    top: usize,
+) -> IndexMap<String, String> {
     let mut formated_summary: IndexMap<String, String> = IndexMap::new();
 
-    for (event_name, event_value) in summary {
-        let event_summary = process_event(&event_value);
+    for (event_name, event_value) in summary.events {
+        let event_summary = process_event(&event_value, parameter_types, top);
         formated_summary.insert(event_name, event_summary);
     }
     formated_summary
 }
 
-fn process_event(event_value: &SumEvent) -> String {
+fn process_event(
+    event_value: &SumEvent,
+    parameter_types: &HashMap<String, String>,
+    top: usize,
+) -> String {
     let mut event_summary = "".to_string();
-    for (parameter_name, parameter_values) in event_value {
-        let mut params: Vec<(&String, &u32)> = parameter_values.iter().collect();
-        params.sort_by(|a, b| b.1.cmp(a.1));
-        let elements: usize = min(5, params.len());
-        let params_counts = merge_parameters(&params[0..elements]);
+    for (parameter_name, parameter_values) in &event_value.parameters {
+        let numeric = is_statistic(parameter_name, parameter_types);
+        let statistics = numeric
+            .then(|| value_statistics(parameter_values, |value| value.parse().ok()))
+            .flatten();
+        let params_counts = match &statistics {
+            Some(statistics) => format_statistics(statistics),
+            _ => {
+                let mut params: Vec<(&String, &u64)> = parameter_values.iter().collect();
+                params.sort_by(|a, b| b.1.cmp(a.1));
+                let elements: usize = min(top, params.len());
+                merge_parameters(&params[0..elements])
+            }
+        };
         let prepared_value = format!("    {:<40}: {}", parameter_name, params_counts);
         event_summary = format!("{}\n{}", event_summary, prepared_value);
+
+        if let (Some(_), Some(column)) = (&statistics, converted_column(parameter_name)) {
+            let converted_statistics = value_statistics(parameter_values, |value| {
+                convert(parameter_name, value).map(|(value, _)| value)
+            });
+            if let Some(statistics) = converted_statistics {
+                let prepared_value =
+                    format!("    {:<40}: {}", column, format_statistics(&statistics));
+                event_summary = format!("{}\n{}", event_summary, prepared_value);
+            }
+        }
     }
     event_summary.to_string()
 }
 
-fn merge_parameters(params: &[(&String, &u32)]) -> String {
+/// Whether a parameter is summarised as statistics: declared as an integer,
+/// and not a UE or cell identifier.
+pub fn is_statistic(name: &str, parameter_types: &HashMap<String, String>) -> bool {
+    parameter_types
+        .get(name)
+        .is_some_and(|parameter_type| is_numeric(parameter_type))
+        && !IDENTIFIERS.contains(&name)
+        && name != GLOBAL_CELL_ID
+}
+
+/// Statistics of counted values, if they can all be read as numbers.
+fn value_statistics(
+    values: &ValueCounts,
+    number: impl Fn(&str) -> Option<f64>,
+) -> Option<Statistics> {
+    let counts = values
+        .iter()
+        .map(|(value, &count)| Some((number(value)?, count)))
+        .collect::<Option<Vec<_>>>()?;
+    counted_statistics(counts)
+}
+
+fn format_statistics(statistics: &Statistics) -> String {
+    format!(
+        "count={} min={} max={} mean={:.2} stddev={:.2} p10={} p50={} p90={}",
+        statistics.count,
+        statistics.min,
+        statistics.max,
+        statistics.mean,
+        statistics.stddev,
+        statistics.p10,
+        statistics.p50,
+        statistics.p90
+    )
+}
+
+fn merge_parameters(params: &[(&String, &u64)]) -> String {
     let mut prepared_value = "".to_string();
 
     for (value_name, value_count) in params {
@@ -43,13 +117,13 @@ mod tests {
 
     #[test]
     fn format() {
-        let mut values: IndexMap<String, u32> = IndexMap::new();
+        let mut values: ValueCounts = IndexMap::new();
         values.insert("value1".to_string(), 1);
         values.insert("value2".to_string(), 2);
-        let mut event: SumEvent = IndexMap::new();
-        event.insert("paramter1".to_string(), values);
-        let mut summary: Summary = IndexMap::new();
-        summary.insert("event1".to_string(), event);
+        let mut event = SumEvent::default();
+        event.parameters.insert("paramter1".to_string(), values);
+        let mut summary = Summary::default();
+        summary.events.insert("event1".to_string(), event);
 
         let mut formatted_summary: IndexMap<String, String> = IndexMap::new();
         let formattd_value = format!(
@@ -57,24 +131,84 @@ mod tests {
             "paramter1", "value2", 2, "value1", 1
         );
         formatted_summary.insert("event1".to_string(), formattd_value);
-        assert_eq!(formatted_summary, format_summary(summary));
+        assert_eq!(
+            formatted_summary,
+            format_summary(summary, &HashMap::new(), DEFAULT_TOP)
+        );
     }
 
     #[test]
     fn format_wrong() {
-        let mut values: IndexMap<String, u32> = IndexMap::new();
+        let mut values: ValueCounts = IndexMap::new();
         values.insert("value1".to_string(), 1);
         values.insert("value2".to_string(), 2);
-        let mut event: SumEvent = IndexMap::new();
-        event.insert("paramter1".to_string(), values);
-        let mut summary: Summary = IndexMap::new();
-        summary.insert("event1".to_string(), event);
+        let mut event = SumEvent::default();
+        event.parameters.insert("paramter1".to_string(), values);
+        let mut summary = Summary::default();
+        summary.events.insert("event1".to_string(), event);
         let mut wrong_formatted_summary: IndexMap<String, String> = IndexMap::new();
         let wrong_formattd_value = format!(
             "\n    {:<40}: ({}:{}) ({}:{}) ",
             "paramter1", "value1", 1, "value2", 2
         );
         wrong_formatted_summary.insert("event1".to_string(), wrong_formattd_value);
-        assert_ne!(wrong_formatted_summary, format_summary(summary));
+        assert_ne!(
+            wrong_formatted_summary,
+            format_summary(summary, &HashMap::new(), DEFAULT_TOP)
+        );
+    }
+
+    #[test]
+    fn format_numeric() {
+        let mut values: ValueCounts = IndexMap::new();
+        values.insert("10".to_string(), 3);
+        values.insert("40".to_string(), 1);
+        let mut event = SumEvent::default();
+        event
+            .parameters
+            .insert("parameter1".to_string(), values.clone());
+        event.parameters.insert("parameter2".to_string(), values);
+        let mut summary = Summary::default();
+        summary.events.insert("event1".to_string(), event);
+        let mut parameter_types = HashMap::new();
+        parameter_types.insert("parameter1".to_string(), "UINT".to_string());
+
+        let formatted = format_summary(summary, &parameter_types, 1);
+        assert_eq!(
+            formatted["event1"],
+            format!(
+                "\n    {:<40}: {}\n    {:<40}: {}",
+                "parameter1",
+                "count=4 min=10 max=40 mean=17.50 stddev=12.99 p10=10 p50=10 p90=40",
+                "parameter2",
+                "(10:3) "
+            )
+        );
+    }
+
+    #[test]
+    fn format_units() {
+        let mut values: ValueCounts = IndexMap::new();
+        values.insert("45".to_string(), 1);
+        let mut event = SumEvent::default();
+        event
+            .parameters
+            .insert("EVENT_PARAM_SERVING_RSRP".to_string(), values);
+        let mut summary = Summary::default();
+        summary.events.insert("event1".to_string(), event);
+        let mut parameter_types = HashMap::new();
+        parameter_types.insert("EVENT_PARAM_SERVING_RSRP".to_string(), "UINT".to_string());
+
+        let formatted = format_summary(summary, &parameter_types, DEFAULT_TOP);
+        assert_eq!(
+            formatted["event1"],
+            format!(
+                "\n    {:<40}: {}\n    {:<40}: {}",
+                "EVENT_PARAM_SERVING_RSRP",
+                "count=1 min=45 max=45 mean=45.00 stddev=0.00 p10=45 p50=45 p90=45",
+                "EVENT_PARAM_SERVING_RSRP_dBm",
+                "count=1 min=-95 max=-95 mean=-95.00 stddev=0.00 p10=-95 p50=-95 p90=-95"
+            )
+        );
     }
 }
@@ -0,0 +1,392 @@
+use super::kpi::{event_cells, is_successful, parameter, KpiCounter, RESULT};
+use super::l3::decode_event;
+use super::session::{build_sessions, event_cause, Session};
+use super::trace_reader::TraceEvent;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const TARGET_CELL_ID: &str = "EVENT_PARAM_HO_TARGET_CELL_ID";
+const SOURCE_CELL_ID: &str = "EVENT_PARAM_HO_SOURCE_CELL_ID";
+const UNKNOWN_CELL: &str = "unknown";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Phase {
+    Preparation,
+    Execution,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Out,
+    In,
+}
+
+/// Handover procedure events, with their phase, direction and interface.
+const HANDOVER_EVENTS: [(&str, Phase, Direction, &str); 8] = [
+    (
+        "INTERNAL_PROC_HO_PREP_X2_OUT",
+        Phase::Preparation,
+        Direction::Out,
+        "x2",
+    ),
+    (
+        "INTERNAL_PROC_HO_EXEC_X2_OUT",
+        Phase::Execution,
+        Direction::Out,
+        "x2",
+    ),
+    (
+        "INTERNAL_PROC_HO_PREP_S1_OUT",
+        Phase::Preparation,
+        Direction::Out,
+        "s1",
+    ),
+    (
+        "INTERNAL_PROC_HO_EXEC_S1_OUT",
+        Phase::Execution,
+        Direction::Out,
+        "s1",
+    ),
+    (
+        "INTERNAL_PROC_HO_PREP_X2_IN",
+        Phase::Preparation,
+        Direction::In,
+        "x2",
+    ),
+    (
+        "INTERNAL_PROC_HO_EXEC_X2_IN",
+        Phase::Execution,
+        Direction::In,
+        "x2",
+    ),
+    (
+        "INTERNAL_PROC_HO_PREP_S1_IN",
+        Phase::Preparation,
+        Direction::In,
+        "s1",
+    ),
+    (
+        "INTERNAL_PROC_HO_EXEC_S1_IN",
+        Phase::Execution,
+        Direction::In,
+        "s1",
+    ),
+];
+
+/// Handovers between two cells as counted by one eNB, split into the
+/// preparation and the execution phase.
+#[derive(Debug, Default, Serialize)]
+pub struct HandoverCounters {
+    pub preparation: KpiCounter,
+    pub execution: KpiCounter,
+}
+
+impl HandoverCounters {
+    /// Executed handovers over prepared ones, in percent.
+    pub fn success_rate(&self) -> Option<f64> {
+        if self.preparation.attempts == 0 {
+            return self.execution.success_rate();
+        }
+        Some(100.0 * self.execution.successes as f64 / self.preparation.attempts as f64)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.preparation.attempts == 0 && self.execution.attempts == 0
+    }
+}
+
+/// Handovers from one cell to another, counted apart by the outgoing events
+/// of the source eNB and the incoming events of the target eNB, so a
+/// handover traced on both sides is not counted twice. A handover between
+/// cells of the same eNB is `intra`, otherwise the kind is the interface it
+/// went through.
+#[derive(Debug, Default, Serialize)]
+pub struct CellPair {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+    pub outgoing: HandoverCounters,
+    pub incoming: HandoverCounters,
+}
+
+impl CellPair {
+    /// The counters of the source eNB, or of the target eNB when only that
+    /// one saw the handovers.
+    pub fn counters(&self) -> &HandoverCounters {
+        if self.outgoing.is_empty() {
+            return &self.incoming;
+        }
+        &self.outgoing
+    }
+
+    pub fn success_rate(&self) -> Option<f64> {
+        self.counters().success_rate()
+    }
+}
+
+/// A UE handed over back to the cell it left within the ping-pong time.
+#[derive(Debug, Serialize)]
+pub struct PingPong {
+    pub ue: String,
+    pub source: String,
+    pub target: String,
+    pub left: u64,
+    pub returned: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HandoverAnalysis {
+    pub pairs: Vec<CellPair>,
+    pub ping_pongs: Vec<PingPong>,
+}
+
+/// A successful handover execution of one UE.
+struct Executed {
+    ue: String,
+    timestamp: u64,
+    source: String,
+    target: String,
+}
+
+/// Handover success per source and target cell pair, and the ping-pong
+/// handovers: a UE returning to its source cell within `ping_pong_ms`.
+/// UEs are followed across eNBs by their MME UE S1AP id, which an X2
+/// handover keeps.
+pub fn analyze_handovers(events: &[TraceEvent], ping_pong_ms: u64) -> HandoverAnalysis {
+    let sessions = build_sessions(events);
+    let cells = event_cells(events, &sessions);
+    let mut pairs: BTreeMap<(String, String), CellPair> = BTreeMap::new();
+    let mut executed = Vec::new();
+
+    for session in &sessions {
+        for &index in &session.events {
+            let event = &events[index];
+            let (phase, direction, interface) = match HANDOVER_EVENTS
+                .iter()
+                .find(|(name, ..)| *name == event.name)
+            {
+                Some(&(_, phase, direction, interface)) => (phase, direction, interface),
+                None => continue,
+            };
+            let own_cell = cells[index].to_string();
+            let (source, target) = match direction {
+                Direction::Out => (own_cell, target_cell(event, session, events)),
+                Direction::In => (
+                    parameter(event, SOURCE_CELL_ID)
+                        .unwrap_or(UNKNOWN_CELL)
+                        .to_string(),
+                    own_cell,
+                ),
+            };
+
+            let pair = pairs
+                .entry((source.to_string(), target.to_string()))
+                .or_insert_with(|| CellPair {
+                    kind: handover_kind(&source, &target, interface),
+                    source: source.to_string(),
+                    target: target.to_string(),
+                    ..Default::default()
+                });
+            let counters = match direction {
+                Direction::Out => &mut pair.outgoing,
+                Direction::In => &mut pair.incoming,
+            };
+            let success = is_successful(event);
+            let cause = event_cause(event)
+                .map(|cause| cause.to_string())
+                .or_else(|| parameter(event, RESULT).map(|result| result.to_string()));
+            match phase {
+                Phase::Preparation => counters.preparation.add(success, cause),
+                Phase::Execution => {
+                    counters.execution.add(success, cause);
+                    if success {
+                        executed.push(Executed {
+                            ue: ue_key(session),
+                            timestamp: event.timestamp,
+                            source,
+                            target,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    HandoverAnalysis {
+        pairs: pairs.into_values().collect(),
+        ping_pongs: ping_pongs(executed, ping_pong_ms),
+    }
+}
+
+fn ping_pongs(mut executed: Vec<Executed>, ping_pong_ms: u64) -> Vec<PingPong> {
+    executed.sort_by(|a, b| a.ue.cmp(&b.ue).then(a.timestamp.cmp(&b.timestamp)));
+    executed
+        .windows(2)
+        .filter(|pair| {
+            let (first, second) = (&pair[0], &pair[1]);
+            first.ue == second.ue
+                && first.source == second.target
+                && first.target == second.source
+                && first.source != UNKNOWN_CELL
+                && second.timestamp - first.timestamp <= ping_pong_ms
+        })
+        .map(|pair| PingPong {
+            ue: pair[0].ue.to_string(),
+            source: pair[0].source.to_string(),
+            target: pair[0].target.to_string(),
+            left: pair[0].timestamp,
+            returned: pair[1].timestamp,
+        })
+        .collect()
+}
+
+/// Target cell of an outgoing handover: the event parameter, or else the
+/// target cell of the last X2 handover request of the session.
+pub fn target_cell(event: &TraceEvent, session: &Session, events: &[TraceEvent]) -> String {
+    if let Some(target) = parameter(event, TARGET_CELL_ID) {
+        return target.to_string();
+    }
+    session
+        .events
+        .iter()
+        .rev()
+        .filter(|&&index| {
+            events[index].name == "X2_HANDOVER_REQUEST"
+                && events[index].timestamp <= event.timestamp
+        })
+        .find_map(|&index| {
+            let message = decode_event(&events[index])?;
+            let cell = message.find("TargetCell-ID")?.find("cell-ID")?;
+            let cell = u64::from_str_radix(cell.value.as_deref()?, 16).ok()?;
+            Some(cell.to_string())
+        })
+        .unwrap_or_else(|| UNKNOWN_CELL.to_string())
+}
+
+/// `intra` when both cells belong to the same eNB, the upper 20 bits of the
+/// 28 bit E-UTRAN cell id, or else the interface of the handover.
+fn handover_kind(source: &str, target: &str, interface: &str) -> String {
+    match (source.parse::<u64>(), target.parse::<u64>()) {
+        (Ok(source), Ok(target)) if source >> 8 == target >> 8 => "intra".to_string(),
+        _ => interface.to_string(),
+    }
+}
+
+/// Identifies a UE across eNBs by its MME UE S1AP id when known.
+fn ue_key(session: &Session) -> String {
+    match session.mme_s1ap_ids.first() {
+        Some(id) => format!("mme-ue-s1ap-id {}", id),
+        None => format!("session {}", session.id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{GLOBAL_CELL_ID, MME_S1AP_ID, RAC_UE_REF};
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_analyze_handovers() {
+        let successful = (RESULT, "EVENT_VALUE_SUCCESSFUL");
+        let events = vec![
+            event(
+                "INTERNAL_PROC_HO_PREP_X2_OUT",
+                1000,
+                &[
+                    (RAC_UE_REF, "1"),
+                    (MME_S1AP_ID, "9"),
+                    (GLOBAL_CELL_ID, "25601"),
+                    (TARGET_CELL_ID, "51201"),
+                    successful,
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_HO_EXEC_X2_OUT",
+                1100,
+                &[(RAC_UE_REF, "1"), (TARGET_CELL_ID, "51201"), successful],
+            ),
+            event(
+                "INTERNAL_PROC_HO_EXEC_X2_IN",
+                3000,
+                &[
+                    (RAC_UE_REF, "2"),
+                    (MME_S1AP_ID, "9"),
+                    (GLOBAL_CELL_ID, "25601"),
+                    (SOURCE_CELL_ID, "51201"),
+                    successful,
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_HO_PREP_X2_OUT",
+                4000,
+                &[
+                    (RAC_UE_REF, "3"),
+                    (GLOBAL_CELL_ID, "25601"),
+                    (TARGET_CELL_ID, "25602"),
+                    (RESULT, "EVENT_VALUE_UNSUCCESSFUL"),
+                ],
+            ),
+        ];
+        let analysis = analyze_handovers(&events, 5000);
+
+        assert_eq!(analysis.pairs.len(), 3);
+        let out = &analysis.pairs[1];
+        assert_eq!(
+            (out.source.as_str(), out.target.as_str()),
+            ("25601", "51201")
+        );
+        assert_eq!(out.kind, "x2");
+        assert_eq!(out.success_rate(), Some(100.0));
+        let intra = &analysis.pairs[0];
+        assert_eq!(intra.kind, "intra");
+        assert_eq!(
+            intra.outgoing.preparation.failure_causes["EVENT_VALUE_UNSUCCESSFUL"],
+            1
+        );
+
+        assert_eq!(analysis.ping_pongs.len(), 1);
+        assert_eq!(analysis.ping_pongs[0].ue, "mme-ue-s1ap-id 9");
+        assert_eq!(analysis.ping_pongs[0].returned, 3000);
+        assert!(analyze_handovers(&events, 1000).ping_pongs.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_handovers_seen_from_both_sides() {
+        let successful = (RESULT, "EVENT_VALUE_SUCCESSFUL");
+        let events = vec![
+            event(
+                "INTERNAL_PROC_HO_PREP_X2_OUT",
+                1000,
+                &[
+                    (RAC_UE_REF, "1"),
+                    (GLOBAL_CELL_ID, "25601"),
+                    (TARGET_CELL_ID, "51201"),
+                    successful,
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_HO_EXEC_X2_OUT",
+                1100,
+                &[(RAC_UE_REF, "1"), (TARGET_CELL_ID, "51201"), successful],
+            ),
+            event(
+                "INTERNAL_PROC_HO_EXEC_X2_IN",
+                1100,
+                &[
+                    (RAC_UE_REF, "7"),
+                    (GLOBAL_CELL_ID, "51201"),
+                    (SOURCE_CELL_ID, "25601"),
+                    successful,
+                ],
+            ),
+        ];
+        let analysis = analyze_handovers(&events, 5000);
+
+        assert_eq!(analysis.pairs.len(), 1);
+        let pair = &analysis.pairs[0];
+        assert_eq!(pair.outgoing.execution.successes, 1);
+        assert_eq!(pair.incoming.execution.successes, 1);
+        assert_eq!(pair.success_rate(), Some(100.0));
+    }
+}
@@ -0,0 +1,522 @@
+use super::l3::decode_event;
+use super::session::{event_cause, Cause, Session, GLOBAL_CELL_ID, RAC_UE_REF};
+use super::trace_reader::TraceEvent;
+use super::units::{convert, format_converted};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub const RESULT: &str = "EVENT_PARAM_RESULT";
+const SUCCESSFUL: [&str; 2] = ["EVENT_VALUE_SUCCESSFUL", "EVENT_VALUE_SUCCESS"];
+const UNKNOWN_CELL: &str = "unknown";
+
+/// Accessibility KPIs and the procedure event each one is computed from:
+/// every event is an attempt, and a successful `EVENT_PARAM_RESULT` makes it
+/// a success.
+const ACCESSIBILITY: [(&str, &str); 4] = [
+    ("rrc_setup", "INTERNAL_PROC_RRC_CONN_SETUP"),
+    ("s1_signalling_setup", "INTERNAL_PROC_S1_SIG_CONN_SETUP"),
+    ("erab_setup", "INTERNAL_PROC_ERAB_SETUP"),
+    ("initial_context_setup", "INTERNAL_PROC_INITIAL_CTXT_SETUP"),
+];
+
+/// Release events for the retainability KPIs: every event is a release, a
+/// success when it is a normal one.
+const RETAINABILITY: [(&str, &str); 2] = [
+    ("ue_context_release", "INTERNAL_PROC_UE_CTXT_RELEASE"),
+    ("erab_release", "INTERNAL_PROC_ERAB_RELEASE"),
+];
+
+/// S1AP release causes, by group and value, that end a connection normally.
+/// Any other S1AP cause is an abnormal release, a drop; a release without a
+/// cause counts as normal.
+const NORMAL_RELEASE_CAUSES: [(&str, &str); 13] = [
+    ("radioNetwork", "successful-handover"),
+    ("radioNetwork", "release-due-to-eutran-generated-reason"),
+    ("radioNetwork", "user-inactivity"),
+    ("radioNetwork", "load-balancing-tau-required"),
+    ("radioNetwork", "cs-fallback-triggered"),
+    ("radioNetwork", "interrat-redirection"),
+    ("radioNetwork", "s1-intra-system-handover-triggered"),
+    ("radioNetwork", "s1-inter-system-handover-triggered"),
+    ("radioNetwork", "x2-handover-triggered"),
+    ("radioNetwork", "redirection-towards-1xRTT"),
+    ("nas", "normal-release"),
+    ("nas", "detach"),
+    ("nas", "csg-subscription-expiry"),
+];
+
+/// Internal release causes and whether each one is a normal release.
+const INTERNAL_RELEASE_CAUSES: [(&str, bool); 12] = [
+    ("EVENT_VALUE_NORMAL_RELEASE", true),
+    ("EVENT_VALUE_UE_INACTIVITY", true),
+    ("EVENT_VALUE_DETACH", true),
+    ("EVENT_VALUE_HANDOVER", true),
+    ("EVENT_VALUE_REDIRECT", true),
+    ("EVENT_VALUE_CS_FALLBACK", true),
+    ("EVENT_VALUE_RADIO_LINK_FAILURE", false),
+    ("EVENT_VALUE_HANDOVER_FAILURE", false),
+    ("EVENT_VALUE_REESTABLISHMENT_FAILURE", false),
+    ("EVENT_VALUE_TRANSPORT_FAILURE", false),
+    ("EVENT_VALUE_PRE_EMPTION", false),
+    ("EVENT_VALUE_OTHER_FAILURE", false),
+];
+
+/// Attempts and successes of a procedure, with the failures counted by
+/// cause.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct KpiCounter {
+    pub attempts: u64,
+    pub successes: u64,
+    pub failure_causes: BTreeMap<String, u64>,
+}
+
+impl KpiCounter {
+    pub fn add(&mut self, success: bool, cause: Option<String>) {
+        self.attempts += 1;
+        if success {
+            self.successes += 1;
+        } else {
+            let cause = cause.unwrap_or_else(|| "unknown".to_string());
+            *self.failure_causes.entry(cause).or_insert(0) += 1;
+        }
+    }
+
+    /// Adds the attempts, successes and failures of `other`.
+    pub fn merge(&mut self, other: &KpiCounter) {
+        self.attempts += other.attempts;
+        self.successes += other.successes;
+        for (cause, count) in &other.failure_causes {
+            *self.failure_causes.entry(cause.to_string()).or_insert(0) += count;
+        }
+    }
+
+    /// Failure rate in percent, if there was any attempt.
+    pub fn failure_rate(&self) -> Option<f64> {
+        self.success_rate().map(|rate| 100.0 - rate)
+    }
+
+    /// Success rate in percent, if there was any attempt.
+    pub fn success_rate(&self) -> Option<f64> {
+        if self.attempts == 0 {
+            return None;
+        }
+        Some(100.0 * self.successes as f64 / self.attempts as f64)
+    }
+}
+
+/// The KPIs of one cell over one time bin.
+#[derive(Debug, Serialize)]
+pub struct CellKpis {
+    pub cell: String,
+    pub bin_start: u64,
+    pub kpis: IndexMap<String, KpiCounter>,
+}
+
+/// Accumulates KPI counters per cell and time bin.
+pub struct KpiTable {
+    bin_ms: u64,
+    cells: BTreeMap<(String, u64), IndexMap<String, KpiCounter>>,
+}
+
+impl KpiTable {
+    pub fn new(bin_ms: u64) -> Self {
+        Self {
+            bin_ms: bin_ms.max(1),
+            cells: BTreeMap::new(),
+        }
+    }
+
+    pub fn counter(&mut self, cell: &str, timestamp: u64, kpi: &str) -> &mut KpiCounter {
+        let bin_start = timestamp - timestamp % self.bin_ms;
+        self.cells
+            .entry((cell.to_string(), bin_start))
+            .or_default()
+            .entry(kpi.to_string())
+            .or_default()
+    }
+
+    pub fn finish(self) -> Vec<CellKpis> {
+        self.cells
+            .into_iter()
+            .map(|((cell, bin_start), kpis)| CellKpis {
+                cell,
+                bin_start,
+                kpis,
+            })
+            .collect()
+    }
+}
+
+/// A UE whose context or E-RAB was released abnormally, with the last
+/// radio measurements of its session before the release. A release outside
+/// any session has no session and only the measurements of its own event.
+#[derive(Debug, Serialize)]
+pub struct DroppedUe {
+    pub session: Option<usize>,
+    pub release: String,
+    pub rac_ue_refs: Vec<String>,
+    pub cell: String,
+    pub timestamp: u64,
+    pub cause: String,
+    pub measurements: IndexMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Retainability {
+    pub cells: Vec<CellKpis>,
+    pub dropped: Vec<DroppedUe>,
+    /// Release causes that could not be classified, counted as normal
+    /// releases.
+    pub unclassified_causes: BTreeMap<String, u64>,
+}
+
+/// Cell of every event. Events without a cell id, such as most S1 events,
+/// take the last cell of their UE session seen so far.
+pub fn event_cells(events: &[TraceEvent], sessions: &[Session]) -> Vec<String> {
+    let mut cells = vec![UNKNOWN_CELL.to_string(); events.len()];
+    for session in sessions {
+        let mut current = UNKNOWN_CELL.to_string();
+        for &index in &session.events {
+            if let Some(cell) = parameter(&events[index], GLOBAL_CELL_ID) {
+                current = cell.to_string();
+            }
+            cells[index] = current.to_string();
+        }
+    }
+    for (index, event) in events.iter().enumerate() {
+        if let Some(cell) = parameter(event, GLOBAL_CELL_ID) {
+            cells[index] = cell.to_string();
+        }
+    }
+    cells
+}
+
+pub fn parameter<'a>(event: &'a TraceEvent, name: &str) -> Option<&'a str> {
+    event
+        .parameters
+        .iter()
+        .find(|parameter| parameter.name == name)
+        .map(|parameter| parameter.value.as_str())
+}
+
+pub fn is_successful(event: &TraceEvent) -> bool {
+    parameter(event, RESULT).is_some_and(|result| SUCCESSFUL.contains(&result))
+}
+
+/// RRC, S1 signalling, E-RAB and initial context setup success per cell
+/// and time bin, with failures broken down by their 3GPP cause, or by the
+/// procedure result when the event carries no cause.
+pub fn accessibility(events: &[TraceEvent], sessions: &[Session], bin_ms: u64) -> Vec<CellKpis> {
+    let cells = event_cells(events, sessions);
+    let kpis: HashMap<&str, &str> = ACCESSIBILITY
+        .iter()
+        .map(|&(kpi, event)| (event, kpi))
+        .collect();
+    let mut table = KpiTable::new(bin_ms);
+
+    for (event, cell) in events.iter().zip(&cells) {
+        if let Some(kpi) = kpis.get(event.name.as_str()) {
+            let cause = event_cause(event)
+                .map(|cause| cause.to_string())
+                .or_else(|| parameter(event, RESULT).map(|result| result.to_string()));
+            table
+                .counter(cell, event.timestamp, kpi)
+                .add(is_successful(event), cause);
+        }
+    }
+    table.finish()
+}
+
+/// UE context and E-RAB releases per cell and time bin, with abnormal
+/// releases counted as failures by cause, and the UEs that dropped. A UE
+/// context release without a cause takes the one of the S1 UE context
+/// release request or command of its session.
+pub fn retainability(events: &[TraceEvent], sessions: &[Session], bin_ms: u64) -> Retainability {
+    let cells = event_cells(events, sessions);
+    let kpis: HashMap<&str, &str> = RETAINABILITY
+        .iter()
+        .map(|&(kpi, event)| (event, kpi))
+        .collect();
+    let mut table = KpiTable::new(bin_ms);
+    let mut dropped = Vec::new();
+    let mut unclassified_causes = BTreeMap::new();
+
+    // The session of every event and the event's position in it.
+    let mut owners = vec![None; events.len()];
+    for session in sessions {
+        for (position, &index) in session.events.iter().enumerate() {
+            owners[index] = Some((session, position));
+        }
+    }
+
+    for (index, event) in events.iter().enumerate() {
+        let kpi = match kpis.get(event.name.as_str()) {
+            Some(kpi) => kpi,
+            None => continue,
+        };
+        let session = owners[index];
+        let cause = match event_cause(event) {
+            None if event.name == "INTERNAL_PROC_UE_CTXT_RELEASE" => {
+                session.and_then(|(session, _)| session.end_cause.clone())
+            }
+            cause => cause,
+        };
+        let normal = match &cause {
+            None => true,
+            Some(cause) => is_normal_release(cause).unwrap_or_else(|| {
+                *unclassified_causes.entry(cause.to_string()).or_insert(0) += 1;
+                true
+            }),
+        };
+        let cause = cause.map(|cause| cause.to_string());
+        table
+            .counter(&cells[index], event.timestamp, kpi)
+            .add(normal, cause.clone());
+        if !normal {
+            let (id, rac_ue_refs, measured) = match session {
+                Some((session, position)) => (
+                    Some(session.id),
+                    session.rac_ue_refs.clone(),
+                    &session.events[..=position],
+                ),
+                None => (
+                    None,
+                    parameter(event, RAC_UE_REF)
+                        .map(|ueref| ueref.to_string())
+                        .into_iter()
+                        .collect(),
+                    std::slice::from_ref(&index),
+                ),
+            };
+            dropped.push(DroppedUe {
+                session: id,
+                release: kpi.to_string(),
+                rac_ue_refs,
+                cell: cells[index].to_string(),
+                timestamp: event.timestamp,
+                cause: cause.unwrap_or_default(),
+                measurements: last_measurements(events, measured),
+            });
+        }
+    }
+    Retainability {
+        cells: table.finish(),
+        dropped,
+        unclassified_causes,
+    }
+}
+
+/// Whether a release cause is a normal one, if it is known.
+fn is_normal_release(cause: &Cause) -> Option<bool> {
+    match cause {
+        Cause::S1ap(group, value) => Some(NORMAL_RELEASE_CAUSES.contains(&(*group, *value))),
+        Cause::Internal(cause) => INTERNAL_RELEASE_CAUSES
+            .iter()
+            .find(|(name, _)| name == cause)
+            .map(|&(_, normal)| normal),
+        Cause::Undecoded(_) => None,
+    }
+}
+
+/// Latest value of each radio measurement parameter in its physical unit,
+/// and the serving cell results of RRC measurement reports, over the given
+/// events.
+fn last_measurements(events: &[TraceEvent], indexes: &[usize]) -> IndexMap<String, String> {
+    let mut measurements = IndexMap::new();
+    for &index in indexes {
+        let event = &events[index];
+        for parameter in &event.parameters {
+            if let Some((value, unit)) = convert(&parameter.name, &parameter.value) {
+                measurements.insert(parameter.name.to_string(), format_converted(value, unit));
+            }
+        }
+        if event.name == "RRC_MEASUREMENT_REPORT" {
+            let serving = decode_event(event);
+            if let Some(serving) = serving.as_ref().and_then(|m| m.find("measResultPCell")) {
+                for (path, value) in serving.flatten() {
+                    measurements.insert(path, value);
+                }
+            }
+        }
+    }
+    measurements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::l3::L3_MESSAGE_CONTENTS;
+    use crate::session::build_sessions;
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_accessibility() {
+        let events = vec![
+            event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                1000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "1"),
+                    (GLOBAL_CELL_ID, "11"),
+                    (RESULT, "EVENT_VALUE_SUCCESSFUL"),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_INITIAL_CTXT_SETUP",
+                1200,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "1"),
+                    (RESULT, "EVENT_VALUE_UNSUCCESSFUL"),
+                    ("EVENT_PARAM_3GPP_CAUSE_GROUP", "EVENT_VALUE_RADIO_NETWORK"),
+                    ("EVENT_PARAM_3GPP_CAUSE", "26"),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                61000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "2"),
+                    (GLOBAL_CELL_ID, "11"),
+                    (RESULT, "EVENT_VALUE_REJECTED"),
+                ],
+            ),
+        ];
+        let kpis = accessibility(&events, &build_sessions(&events), 60000);
+
+        assert_eq!(kpis.len(), 2);
+        assert_eq!((kpis[0].cell.as_str(), kpis[0].bin_start), ("11", 0));
+        assert_eq!(kpis[0].kpis["rrc_setup"].success_rate(), Some(100.0));
+        let context = &kpis[0].kpis["initial_context_setup"];
+        assert_eq!((context.attempts, context.successes), (1, 0));
+        assert_eq!(
+            context.failure_causes["radioNetwork: failure-in-radio-interface-procedure"],
+            1
+        );
+        assert_eq!(kpis[1].bin_start, 60000);
+        assert_eq!(
+            kpis[1].kpis["rrc_setup"].failure_causes["EVENT_VALUE_REJECTED"],
+            1
+        );
+    }
+
+    #[test]
+    fn test_retainability() {
+        let events = vec![
+            event(
+                "INTERNAL_PER_RADIO_UE_MEASUREMENT",
+                1000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "1"),
+                    ("EVENT_PARAM_SERVING_RSRP", "40"),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                2000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "1"),
+                    (GLOBAL_CELL_ID, "11"),
+                    ("EVENT_PARAM_3GPP_CAUSE_GROUP", "EVENT_VALUE_RADIO_NETWORK"),
+                    ("EVENT_PARAM_3GPP_CAUSE", "21"),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                3000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "2"),
+                    (GLOBAL_CELL_ID, "11"),
+                    ("EVENT_PARAM_3GPP_CAUSE_GROUP", "EVENT_VALUE_RADIO_NETWORK"),
+                    ("EVENT_PARAM_3GPP_CAUSE", "20"),
+                ],
+            ),
+        ];
+        let retainability = retainability(&events, &build_sessions(&events), 60000);
+
+        let releases = &retainability.cells[0].kpis["ue_context_release"];
+        assert_eq!((releases.attempts, releases.successes), (2, 1));
+        assert_eq!(releases.failure_rate(), Some(50.0));
+        assert_eq!(retainability.dropped.len(), 1);
+        let dropped = &retainability.dropped[0];
+        assert_eq!(dropped.cause, "radioNetwork: radio-connection-with-ue-lost");
+        assert_eq!(dropped.measurements["EVENT_PARAM_SERVING_RSRP"], "-100 dBm");
+    }
+
+    #[test]
+    fn test_retainability_cause_classification() {
+        let events = vec![
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                1000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "1"),
+                    (GLOBAL_CELL_ID, "11"),
+                    (
+                        "EVENT_PARAM_INTERNAL_RELEASE_CAUSE",
+                        "EVENT_VALUE_UE_INACTIVITY",
+                    ),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                2000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "2"),
+                    (GLOBAL_CELL_ID, "11"),
+                    ("EVENT_PARAM_3GPP_CAUSE_GROUP", "EVENT_VALUE_RADIO_NETWORK"),
+                    ("EVENT_PARAM_3GPP_CAUSE", "99"),
+                ],
+            ),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                3000,
+                &[
+                    (GLOBAL_CELL_ID, "11"),
+                    (
+                        "EVENT_PARAM_INTERNAL_RELEASE_CAUSE",
+                        "EVENT_VALUE_RADIO_LINK_FAILURE",
+                    ),
+                ],
+            ),
+        ];
+        let retainability = retainability(&events, &build_sessions(&events), 60000);
+
+        let releases = &retainability.cells[0].kpis["ue_context_release"];
+        assert_eq!((releases.attempts, releases.successes), (3, 2));
+        assert_eq!(
+            retainability.unclassified_causes["EVENT_VALUE_RADIO_NETWORK: 99"],
+            1
+        );
+        assert_eq!(retainability.dropped.len(), 1);
+        let dropped = &retainability.dropped[0];
+        assert_eq!(dropped.session, None);
+        assert_eq!(dropped.cause, "EVENT_VALUE_RADIO_LINK_FAILURE");
+    }
+
+    #[test]
+    fn test_retainability_release_without_cause() {
+        // S1 handover required, cause handover-desirable-for-radio-reason
+        let handover = (
+            L3_MESSAGE_CONTENTS,
+            "000040110000020063000400010005000240020200",
+        );
+        let events = vec![
+            event(
+                "S1_HANDOVER_REQUIRED",
+                1000,
+                &[("EVENT_PARAM_RAC_UE_REF", "1"), handover],
+            ),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                2000,
+                &[("EVENT_PARAM_RAC_UE_REF", "1"), (GLOBAL_CELL_ID, "11")],
+            ),
+        ];
+        let retainability = retainability(&events, &build_sessions(&events), 60000);
+
+        let releases = &retainability.cells[0].kpis["ue_context_release"];
+        assert_eq!((releases.attempts, releases.successes), (1, 1));
+        assert!(retainability.dropped.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use super::rrc;
+use super::s1ap;
+use super::trace_reader::TraceEvent;
+use super::x2ap;
+use serde::Serialize;
+
+pub const L3_MESSAGE_CONTENTS: &str = "EVENT_PARAM_L3MESSAGE_CONTENTS";
+pub const MESSAGE_DIRECTION: &str = "EVENT_PARAM_MESSAGE_DIRECTION";
+pub const DIRECTION_SENT: &str = "EVENT_VALUE_SENT";
+pub const DIRECTION_RECEIVED: &str = "EVENT_VALUE_RECEIVED";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RrcChannel {
+    UlCcch,
+    DlCcch,
+    UlDcch,
+    DlDcch,
+    BcchBch,
+    BcchDlSch,
+    Pcch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum L3Protocol {
+    Rrc(RrcChannel),
+    S1ap,
+    X2ap,
+}
+
+impl L3Protocol {
+    /// Name of the Wireshark dissector for this protocol.
+    pub fn dissector(&self) -> &'static str {
+        match self {
+            L3Protocol::Rrc(RrcChannel::UlCcch) => "lte_rrc.ul_ccch",
+            L3Protocol::Rrc(RrcChannel::DlCcch) => "lte_rrc.dl_ccch",
+            L3Protocol::Rrc(RrcChannel::UlDcch) => "lte_rrc.ul_dcch",
+            L3Protocol::Rrc(RrcChannel::DlDcch) => "lte_rrc.dl_dcch",
+            L3Protocol::Rrc(RrcChannel::BcchBch) => "lte_rrc.bcch_bch",
+            L3Protocol::Rrc(RrcChannel::BcchDlSch) => "lte_rrc.bcch_dl_sch",
+            L3Protocol::Rrc(RrcChannel::Pcch) => "lte_rrc.pcch",
+            L3Protocol::S1ap => "s1ap",
+            L3Protocol::X2ap => "x2ap",
+        }
+    }
+}
+
+/// An L3 message carried in an event, with the protocol it is encoded in.
+pub struct L3Message {
+    pub protocol: L3Protocol,
+    pub sent: bool,
+    pub contents: Vec<u8>,
+}
+
+/// A node of a decoded L3 message: a named value, a group of nested fields,
+/// or just a name for elements that carry no value.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct L3Field {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<L3Field>,
+}
+
+impl L3Field {
+    pub fn new(name: &str) -> L3Field {
+        L3Field {
+            name: name.to_string(),
+            value: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn leaf<T: ToString>(name: &str, value: T) -> L3Field {
+        L3Field {
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds an empty child node and returns it so it can be filled in.
+    pub fn child(&mut self, name: &str) -> &mut L3Field {
+        self.children.push(L3Field::new(name));
+        self.children.last_mut().unwrap()
+    }
+
+    pub fn push(&mut self, field: L3Field) {
+        self.children.push(field);
+    }
+
+    /// First field with the given name, searching depth first.
+    pub fn find(&self, name: &str) -> Option<&L3Field> {
+        if self.name == name {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(name))
+    }
+
+    /// Fields as `(path, value)` pairs, with the path made of the dotted
+    /// names from the root. Nodes without children are listed even when
+    /// they have no value.
+    pub fn flatten(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        self.flatten_into(&self.name, &mut fields);
+        fields
+    }
+
+    fn flatten_into(&self, path: &str, fields: &mut Vec<(String, String)>) {
+        if self.value.is_some() || self.children.is_empty() {
+            fields.push((path.to_string(), self.value.clone().unwrap_or_default()));
+        }
+        for child in &self.children {
+            child.flatten_into(&format!("{}.{}", path, child.name), fields);
+        }
+    }
+}
+
+/// Works out the protocol of an event's L3 message from the event name and
+/// the message direction. `sent` means sent by the eNB.
+pub fn classify(event_name: &str, sent: bool) -> Option<L3Protocol> {
+    if event_name.starts_with("S1_") {
+        return Some(L3Protocol::S1ap);
+    }
+    if event_name.starts_with("X2_") {
+        return Some(L3Protocol::X2ap);
+    }
+    let message = event_name.strip_prefix("RRC_")?;
+    let channel = match message {
+        "RRC_CONNECTION_REQUEST" | "RRC_CONNECTION_REESTABLISHMENT_REQUEST" => RrcChannel::UlCcch,
+        "RRC_CONNECTION_SETUP"
+        | "RRC_CONNECTION_REJECT"
+        | "RRC_CONNECTION_REESTABLISHMENT"
+        | "RRC_CONNECTION_REESTABLISHMENT_REJECT" => RrcChannel::DlCcch,
+        "MASTER_INFORMATION_BLOCK" => RrcChannel::BcchBch,
+        "PAGING" => RrcChannel::Pcch,
+        _ if message.starts_with("SYSTEM_INFORMATION") => RrcChannel::BcchDlSch,
+        _ if sent => RrcChannel::DlDcch,
+        _ => RrcChannel::UlDcch,
+    };
+    Some(L3Protocol::Rrc(channel))
+}
+
+/// Extracts the L3 message of an event, if it carries one of a known protocol.
+pub fn l3_message(event: &TraceEvent) -> Option<L3Message> {
+    let contents = event
+        .parameters
+        .iter()
+        .find(|p| p.name == L3_MESSAGE_CONTENTS)?;
+    let contents = hex::decode(&contents.value).ok()?;
+    if contents.is_empty() {
+        return None;
+    }
+    let sent = event
+        .parameters
+        .iter()
+        .any(|p| p.name == MESSAGE_DIRECTION && p.value == DIRECTION_SENT);
+
+    Some(L3Message {
+        protocol: classify(&event.name, sent)?,
+        sent,
+        contents,
+    })
+}
+
+/// Decodes the contents of an L3 message, for the protocols we have a
+/// decoder for.
+pub fn decode(message: &L3Message) -> Option<L3Field> {
+    match message.protocol {
+        L3Protocol::Rrc(channel) => Some(rrc::decode(channel, &message.contents)),
+        L3Protocol::S1ap => Some(s1ap::decode(&message.contents)),
+        L3Protocol::X2ap => Some(x2ap::decode(&message.contents)),
+    }
+}
+
+/// The cause carried by a decoded message, such as the S1AP or X2AP
+/// `Cause` IE, the RRC release cause or a NAS EMM or ESM cause, as
+/// `group: value` when the cause is grouped.
+pub fn cause(message: &L3Field) -> Option<String> {
+    if let Some(cause) = message.find("Cause") {
+        let value = cause.children.first()?;
+        return Some(match &value.value {
+            Some(text) => format!("{}: {}", value.name, text),
+            None => value.name.to_string(),
+        });
+    }
+    ["releaseCause", "emmCause", "esmCause"]
+        .iter()
+        .find_map(|name| message.find(name)?.value.clone())
+}
+
+/// Decodes the L3 message of an event, if it carries one we can decode.
+pub fn decode_event(event: &TraceEvent) -> Option<L3Field> {
+    decode(&l3_message(event)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("RRC_RRC_CONNECTION_REQUEST", false),
+            Some(L3Protocol::Rrc(RrcChannel::UlCcch))
+        );
+        assert_eq!(
+            classify("RRC_RRC_CONNECTION_RECONFIGURATION", true),
+            Some(L3Protocol::Rrc(RrcChannel::DlDcch))
+        );
+        assert_eq!(
+            classify("RRC_MEASUREMENT_REPORT", false),
+            Some(L3Protocol::Rrc(RrcChannel::UlDcch))
+        );
+        assert_eq!(
+            classify("RRC_SYSTEM_INFORMATION_BLOCK_TYPE_1", true),
+            Some(L3Protocol::Rrc(RrcChannel::BcchDlSch))
+        );
+        assert_eq!(
+            classify("S1_INITIAL_CONTEXT_SETUP_REQUEST", false),
+            Some(L3Protocol::S1ap)
+        );
+        assert_eq!(classify("INTERNAL_PROC_RRC_CONN_SETUP", false), None);
+    }
+}
@@ -0,0 +1,381 @@
+use super::l3::{cause, decode_event, DIRECTION_RECEIVED, DIRECTION_SENT, MESSAGE_DIRECTION};
+use super::session::{format_time, Session};
+use super::trace_reader::TraceEvent;
+
+const UE: usize = 0;
+const ENB: usize = 1;
+const MME: usize = 2;
+const PEER_ENB: usize = 3;
+
+const TIME_WIDTH: usize = 14;
+const TEXT_LANE_WIDTH: usize = 28;
+const SVG_LANE_WIDTH: usize = 220;
+const SVG_ROW_HEIGHT: usize = 28;
+const SVG_TIME_WIDTH: usize = 110;
+
+/// One message between two lanes, or a note on a lane when `from` and `to`
+/// are the same.
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    pub timestamp: u64,
+    pub from: usize,
+    pub to: usize,
+    pub message: String,
+}
+
+/// The call flow of a UE session, ready to be rendered.
+pub struct Ladder {
+    pub title: String,
+    pub lanes: Vec<&'static str>,
+    pub steps: Vec<Step>,
+}
+
+impl Ladder {
+    /// Lanes that appear in at least one step, in lane order; the UE and
+    /// the eNB are always shown.
+    fn used_lanes(&self) -> Vec<usize> {
+        (0..self.lanes.len())
+            .filter(|&lane| {
+                lane <= ENB
+                    || self
+                        .steps
+                        .iter()
+                        .any(|step| step.from == lane || step.to == lane)
+            })
+            .collect()
+    }
+}
+
+/// Builds the ladder of a session: RRC messages between the UE and the eNB,
+/// S1 messages between the eNB and the MME, X2 messages between the eNB and
+/// the peer eNB, and internal procedures as notes on the eNB.
+pub fn build_ladder(session: &Session, events: &[TraceEvent]) -> Ladder {
+    let peer = if session.handover_in {
+        "source eNB"
+    } else {
+        "target eNB"
+    };
+    let steps = session
+        .events
+        .iter()
+        .filter_map(|&index| step(&events[index]))
+        .collect();
+    Ladder {
+        title: format!(
+            "session {} (RAC UE ref {})",
+            session.id,
+            session.rac_ue_refs.join(", ")
+        ),
+        lanes: vec!["UE", "eNB", "MME", peer],
+        steps,
+    }
+}
+
+fn step(event: &TraceEvent) -> Option<Step> {
+    let direction = event
+        .parameters
+        .iter()
+        .find(|parameter| parameter.name == MESSAGE_DIRECTION)
+        .map(|parameter| parameter.value.as_str());
+    let (remote, name) = if let Some(name) = event.name.strip_prefix("RRC_") {
+        (UE, name)
+    } else if let Some(name) = event.name.strip_prefix("S1_") {
+        (MME, name)
+    } else if let Some(name) = event.name.strip_prefix("X2_") {
+        (PEER_ENB, name)
+    } else if event.name.starts_with("INTERNAL_PROC_") {
+        (ENB, event.name.as_str())
+    } else {
+        return None;
+    };
+    let (from, to) = match direction {
+        Some(DIRECTION_SENT) => (ENB, remote),
+        Some(DIRECTION_RECEIVED) => (remote, ENB),
+        _ => (ENB, ENB),
+    };
+    let message = match decode_event(event).as_ref().and_then(cause) {
+        Some(cause) => format!("{} ({})", name, cause),
+        None => name.to_string(),
+    };
+    Some(Step {
+        timestamp: event.timestamp,
+        from,
+        to,
+        message,
+    })
+}
+
+/// Plain text ladder with one column per lane.
+pub fn render_text(ladder: &Ladder) -> String {
+    let lanes = ladder.used_lanes();
+    let column = |lane: usize| {
+        TIME_WIDTH + lanes.iter().position(|&used| used == lane).unwrap() * TEXT_LANE_WIDTH
+    };
+    let width = column(*lanes.last().unwrap()) + 1;
+    let lifelines = || {
+        let mut line = vec![' '; width];
+        for &lane in &lanes {
+            line[column(lane)] = '|';
+        }
+        line
+    };
+
+    let mut output = format!("{}\n", ladder.title);
+    let mut header = vec![' '; width + TEXT_LANE_WIDTH];
+    for &lane in &lanes {
+        let name = ladder.lanes[lane];
+        let start = column(lane).saturating_sub(name.len() / 2);
+        header.splice(start..start + name.len(), name.chars());
+    }
+    output.push_str(header.iter().collect::<String>().trim_end());
+    output.push('\n');
+
+    for step in &ladder.steps {
+        let left = column(step.from).min(column(step.to));
+        let right = column(step.from).max(column(step.to));
+        let mut label = lifelines();
+        label.splice(0..12, format_time(step.timestamp).chars());
+        let text = if step.from == step.to {
+            format!("[{}]", step.message)
+        } else {
+            step.message.to_string()
+        };
+        let start = left + 2;
+        if label.len() < start + text.len() {
+            label.resize(start + text.len(), ' ');
+        }
+        label.splice(start..start + text.len(), text.chars());
+        output.push_str(label.iter().collect::<String>().trim_end());
+        output.push('\n');
+
+        if step.from != step.to {
+            let mut arrow = lifelines();
+            for cell in &mut arrow[left + 1..right] {
+                *cell = '-';
+            }
+            if column(step.to) > column(step.from) {
+                arrow[right - 1] = '>';
+            } else {
+                arrow[left + 1] = '<';
+            }
+            output.push_str(arrow.iter().collect::<String>().trim_end());
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// One SVG document with the ladders stacked vertically.
+pub fn render_svg(ladders: &[Ladder]) -> String {
+    let mut body = String::new();
+    let mut top = 0;
+    let mut width = 0;
+    for ladder in ladders {
+        let lanes = ladder.used_lanes();
+        let x = |lane: usize| {
+            SVG_TIME_WIDTH
+                + lanes.iter().position(|&used| used == lane).unwrap() * SVG_LANE_WIDTH
+                + SVG_LANE_WIDTH / 2
+        };
+        let height = (ladder.steps.len() + 3) * SVG_ROW_HEIGHT;
+        width = width.max(SVG_TIME_WIDTH + lanes.len() * SVG_LANE_WIDTH);
+
+        body.push_str(&format!(
+            "<g transform=\"translate(0,{})\">\n<text x=\"4\" y=\"18\" font-weight=\"bold\">{}</text>\n",
+            top,
+            escape(&ladder.title)
+        ));
+        for &lane in &lanes {
+            body.push_str(&format!(
+                "<text x=\"{0}\" y=\"{1}\" text-anchor=\"middle\">{2}</text>\n<line x1=\"{0}\" y1=\"{3}\" x2=\"{0}\" y2=\"{4}\" stroke=\"gray\"/>\n",
+                x(lane),
+                2 * SVG_ROW_HEIGHT - 8,
+                escape(ladder.lanes[lane]),
+                2 * SVG_ROW_HEIGHT,
+                height
+            ));
+        }
+        for (row, step) in ladder.steps.iter().enumerate() {
+            let y = (row + 3) * SVG_ROW_HEIGHT;
+            body.push_str(&format!(
+                "<text x=\"4\" y=\"{}\" fill=\"gray\">{}</text>\n",
+                y,
+                format_time(step.timestamp)
+            ));
+            if step.from == step.to {
+                body.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-style=\"italic\">{}</text>\n",
+                    x(step.from) + 6,
+                    y,
+                    escape(&step.message)
+                ));
+                continue;
+            }
+            let (from, to) = (x(step.from), x(step.to));
+            body.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" marker-end=\"url(#arrow)\"/>\n<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                from,
+                y,
+                to,
+                y,
+                (from + to) / 2,
+                y - 5,
+                escape(&step.message)
+            ));
+        }
+        body.push_str("</g>\n");
+        top += height + SVG_ROW_HEIGHT;
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"monospace\" font-size=\"12\">\n<defs><marker id=\"arrow\" markerWidth=\"8\" markerHeight=\"8\" refX=\"8\" refY=\"4\" orient=\"auto\"><path d=\"M0,0 L8,4 L0,8 z\"/></marker></defs>\n{}</svg>\n",
+        width, top, body
+    )
+}
+
+/// PlantUML sequence diagram.
+pub fn render_plantuml(ladder: &Ladder) -> String {
+    let mut output = format!("@startuml\ntitle {}\n", ladder.title);
+    for lane in ladder.used_lanes() {
+        output.push_str(&format!(
+            "participant \"{}\" as L{}\n",
+            ladder.lanes[lane], lane
+        ));
+    }
+    for step in &ladder.steps {
+        let time = format_time(step.timestamp);
+        if step.from == step.to {
+            output.push_str(&format!(
+                "note over L{} : {} {}\n",
+                step.from, time, step.message
+            ));
+        } else {
+            output.push_str(&format!(
+                "L{} -> L{} : {} {}\n",
+                step.from, step.to, time, step.message
+            ));
+        }
+    }
+    output.push_str("@enduml\n");
+    output
+}
+
+/// Mermaid sequence diagram, fenced so it can be pasted into a Markdown
+/// report as is.
+pub fn render_mermaid(ladder: &Ladder) -> String {
+    let mut output = format!("```mermaid\nsequenceDiagram\n    title {}\n", ladder.title);
+    for lane in ladder.used_lanes() {
+        output.push_str(&format!(
+            "    participant L{} as {}\n",
+            lane, ladder.lanes[lane]
+        ));
+    }
+    for step in &ladder.steps {
+        // Mermaid ends the message text at a semicolon or `#`.
+        let message = step.message.replace([';', '#'], " ");
+        let time = format_time(step.timestamp);
+        if step.from == step.to {
+            output.push_str(&format!(
+                "    Note over L{}: {} {}\n",
+                step.from, time, message
+            ));
+        } else {
+            output.push_str(&format!(
+                "    L{}->>L{}: {} {}\n",
+                step.from, step.to, time, message
+            ));
+        }
+    }
+    output.push_str("```\n");
+    output
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{build_sessions, RAC_UE_REF};
+    use crate::trace_reader::event;
+
+    fn ladder() -> Ladder {
+        let events = vec![
+            event(
+                "RRC_RRC_CONNECTION_REQUEST",
+                1000,
+                &[(RAC_UE_REF, "7"), (MESSAGE_DIRECTION, DIRECTION_RECEIVED)],
+            ),
+            event("INTERNAL_PROC_RRC_CONN_SETUP", 1010, &[(RAC_UE_REF, "7")]),
+            event(
+                "S1_INITIAL_UE_MESSAGE",
+                1020,
+                &[(RAC_UE_REF, "7"), (MESSAGE_DIRECTION, DIRECTION_SENT)],
+            ),
+            event(
+                "INTERNAL_PER_RADIO_UE_MEASUREMENT",
+                1030,
+                &[(RAC_UE_REF, "7")],
+            ),
+        ];
+        let sessions = build_sessions(&events);
+        build_ladder(&sessions[0], &events)
+    }
+
+    #[test]
+    fn test_build_ladder() {
+        let ladder = ladder();
+        assert_eq!(
+            ladder.steps,
+            vec![
+                Step {
+                    timestamp: 1000,
+                    from: UE,
+                    to: ENB,
+                    message: "RRC_CONNECTION_REQUEST".to_string()
+                },
+                Step {
+                    timestamp: 1010,
+                    from: ENB,
+                    to: ENB,
+                    message: "INTERNAL_PROC_RRC_CONN_SETUP".to_string()
+                },
+                Step {
+                    timestamp: 1020,
+                    from: ENB,
+                    to: MME,
+                    message: "INITIAL_UE_MESSAGE".to_string()
+                },
+            ]
+        );
+        assert_eq!(ladder.used_lanes(), vec![UE, ENB, MME]);
+    }
+
+    #[test]
+    fn test_render() {
+        let ladder = ladder();
+        let text = render_text(&ladder);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "session 1 (RAC UE ref 7)");
+        assert_eq!(
+            lines[1..],
+            [
+                "             UE                          eNB                         MME",
+                "00:00:01.000  | RRC_CONNECTION_REQUEST    |                           |",
+                "              |-------------------------->|                           |",
+                "00:00:01.010  |                           | [INTERNAL_PROC_RRC_CONN_SETUP]",
+                "00:00:01.020  |                           | INITIAL_UE_MESSAGE        |",
+                "              |                           |-------------------------->|",
+            ]
+        );
+
+        let mermaid = render_mermaid(&ladder);
+        assert!(mermaid.contains("    L0->>L1: 00:00:01.000 RRC_CONNECTION_REQUEST\n"));
+        assert!(render_plantuml(&ladder)
+            .contains("note over L1 : 00:00:01.010 INTERNAL_PROC_RRC_CONN_SETUP\n"));
+        assert!(render_svg(&[ladder]).starts_with("<svg"));
+    }
+}
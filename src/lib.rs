@@ -1,39 +1,117 @@
-use cpython::{py_fn, py_module_initializer, PyDict, PyResult, Python};
+use arrow::array::{Array, StructArray};
+use arrow::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use cpython::{
+    exc, py_fn, py_module_initializer, ObjectProtocol, PyDict, PyErr, PyList, PyModule, PyResult,
+    Python, PythonObject, ToPyObject,
+};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use writer::{EventWriter, JsonLinesWriter};
 
 use config::{Config, Processing};
 
+pub mod columnar;
+pub mod compare;
 pub mod config;
 pub mod converter;
 pub mod ctr_analyzer;
+pub mod distance;
 pub mod formatter;
+pub mod handover;
+pub mod kpi;
+pub mod l3;
+pub mod ladder;
+pub mod nas;
+pub mod neighbour;
 pub mod parser;
+pub mod pcap_writer;
+pub mod per;
+pub mod rrc;
+pub mod s1ap;
+pub mod schema;
+pub mod schema_diff;
+pub mod session;
+pub mod sqlite_writer;
+pub mod stats;
+pub mod timeseries;
 pub mod trace_reader;
+pub mod units;
+pub mod writer;
+pub mod x2ap;
 pub mod xml_parser;
 
 py_module_initializer!(rust_parser, |py, m| {
     m.add(py, "__doc__", "This module is implemented in Rust.")?;
+    add_functions(py, m)
+});
+
+// `py_fn!` expands to a manual prefix strip that clippy cannot see past.
+#[allow(clippy::manual_strip)]
+fn add_functions(py: Python, m: &PyModule) -> PyResult<()> {
     m.add(
         py,
         "parse_celltrace",
         py_fn!(py, parse_celltrace(xml: &str, filename: &str)),
     )?;
+    m.add(py, "schema_events", py_fn!(py, schema_events(xml: &str)))?;
+    m.add(
+        py,
+        "schema_search",
+        py_fn!(py, schema_search(xml: &str, pattern: &str)),
+    )?;
+    m.add(
+        py,
+        "schema_validate",
+        py_fn!(py, schema_validate(xml: &str)),
+    )?;
+    m.add(
+        py,
+        "export_jsonl",
+        py_fn!(py, export_jsonl(xml: &str, filename: &str, output: &str)),
+    )?;
+    m.add(
+        py,
+        "time_series",
+        py_fn!(py, time_series(xml: &str, filename: &str, bin: &str)),
+    )?;
+    m.add(
+        py,
+        "record_batches",
+        py_fn!(py, record_batches(xml: &str, filename: &str)),
+    )?;
     Ok(())
-});
+}
 
-fn parse_celltrace(py: Python, xml: &str, filename: &str) -> PyResult<PyDict> {
-    let config = Config {
+fn trace_config(xml: &str, filename: &str, output: Processing) -> Config {
+    Config {
         xml: xml.to_string(),
         filename: filename.to_string(),
-        output: Processing::Summary,
+        output,
         filter: "all".to_string(),
         ueref: "all".to_string(),
-    };
+        log_level: log::LevelFilter::Off,
+        output_path: None,
+        bin_ms: config::DEFAULT_BIN_MS,
+        ping_pong_ms: config::DEFAULT_PING_PONG_MS,
+        distance_bin_m: config::DEFAULT_DISTANCE_BIN_M,
+        radius_m: config::DEFAULT_RADIUS_M,
+        top: formatter::DEFAULT_TOP,
+        summary_scope: ctr_analyzer::SummaryScope::default(),
+    }
+}
+
+fn parse_celltrace(py: Python, xml: &str, filename: &str) -> PyResult<PyDict> {
+    let config = trace_config(xml, filename, Processing::Summary);
 
-    let mut parser = trace_reader::TraceReader::new(&config);
+    let mut parser = trace_reader::TraceReader::new(&config)
+        .map_err(|diagnostics| schema_error(py, xml, diagnostics))?;
+    let parameter_types = schema::parameter_types(parser.events_definition());
     parser.read_trace();
 
-    let summary = ctr_analyzer::summarize_trace(&parser.decoded_trace_events);
-    let result = formatter::format_summary(summary);
+    let summary =
+        ctr_analyzer::summarize_trace(&parser.decoded_trace_events, &config.summary_scope);
+    let result = formatter::format_summary(summary, &parameter_types, config.top);
 
     let locals = PyDict::new(py);
 
@@ -43,3 +121,152 @@ fn parse_celltrace(py: Python, xml: &str, filename: &str) -> PyResult<PyDict> {
 
     Ok(locals)
 }
+
+fn export_jsonl(py: Python, xml: &str, filename: &str, output: &str) -> PyResult<bool> {
+    let config = trace_config(xml, filename, Processing::JsonLines);
+
+    let mut parser = trace_reader::TraceReader::new(&config)
+        .map_err(|diagnostics| schema_error(py, xml, diagnostics))?;
+    let parameter_types = schema::parameter_types(parser.events_definition());
+
+    let result = File::create(output).and_then(|file| {
+        let mut writer = JsonLinesWriter::new(BufWriter::new(file), filename, parameter_types);
+        parser.read_trace_with(|event| writer.write(&event))?;
+        writer.finish()
+    });
+    result.map_err(|err| PyErr::new::<exc::IOError, _>(py, err.to_string()))?;
+
+    Ok(true)
+}
+
+/// Event counts and numeric parameter statistics per time bin and cell, as
+/// lists of tuples under `event_counts` and `parameters`. `bin` is a
+/// duration like `1s`, `1m` or `15m`.
+fn time_series(py: Python, xml: &str, filename: &str, bin: &str) -> PyResult<PyDict> {
+    let bin_ms = config::parse_duration(bin)
+        .ok_or_else(|| PyErr::new::<exc::ValueError, _>(py, format!("invalid bin size {}", bin)))?;
+    let config = trace_config(xml, filename, Processing::Series { csv: false });
+
+    let mut parser = trace_reader::TraceReader::new(&config)
+        .map_err(|diagnostics| schema_error(py, xml, diagnostics))?;
+    let parameter_types = schema::parameter_types(parser.events_definition());
+    parser.read_trace();
+    let series = timeseries::time_series(&parser.decoded_trace_events, &parameter_types, bin_ms);
+
+    let event_counts = PyList::new(py, &[]);
+    for count in series.event_counts {
+        let row = (count.bin_start, count.cell, count.event, count.count).to_py_object(py);
+        event_counts.append(py, row.into_object());
+    }
+    let parameters = PyList::new(py, &[]);
+    for parameter in series.parameters {
+        let statistics = parameter.statistics;
+        let row = (
+            parameter.bin_start,
+            parameter.cell,
+            parameter.event,
+            parameter.parameter,
+            statistics.count,
+            statistics.min,
+            statistics.mean,
+            statistics.max,
+            (statistics.p10, statistics.p50, statistics.p90),
+        )
+            .to_py_object(py);
+        parameters.append(py, row.into_object());
+    }
+
+    let result = PyDict::new(py);
+    result.set_item(py, "event_counts", event_counts)?;
+    result.set_item(py, "parameters", parameters)?;
+    Ok(result)
+}
+
+/// Decodes the trace into one `pyarrow.RecordBatch` per event type. The
+/// batches are handed over through the Arrow C data interface, so pyarrow
+/// takes ownership of the Rust buffers instead of copying them.
+fn record_batches(py: Python, xml: &str, filename: &str) -> PyResult<PyDict> {
+    let config = trace_config(xml, filename, Processing::Parquet);
+
+    let mut parser = trace_reader::TraceReader::new(&config)
+        .map_err(|diagnostics| schema_error(py, xml, diagnostics))?;
+    let mut tables = columnar::ArrowTables::new(parser.events_definition());
+    let _ = parser.read_trace_with(|event| {
+        tables.append(&event);
+        Ok(())
+    });
+    let batches = tables.finish().map_err(|err| arrow_error(py, err))?;
+
+    let record_batch = py.import("pyarrow")?.get(py, "RecordBatch")?;
+    let result = PyDict::new(py);
+
+    for (name, batch) in batches {
+        let data = StructArray::from(batch).into_data();
+        let (array, schema) = to_ffi(&data).map_err(|err| arrow_error(py, err))?;
+        let array = Box::new(array);
+        let schema = Box::new(schema);
+        let array_address = &*array as *const FFI_ArrowArray as usize;
+        let schema_address = &*schema as *const FFI_ArrowSchema as usize;
+
+        let imported = record_batch.call_method(
+            py,
+            "_import_from_c",
+            (array_address, schema_address),
+            None,
+        )?;
+        result.set_item(py, name, imported)?;
+    }
+
+    Ok(result)
+}
+
+fn arrow_error(py: Python, err: arrow::error::ArrowError) -> PyErr {
+    PyErr::new::<exc::ValueError, _>(py, err.to_string())
+}
+
+fn schema_events(py: Python, xml: &str) -> PyResult<PyList> {
+    let events = load_schema(py, xml)?;
+    let result = PyList::new(py, &[]);
+
+    for summary in schema::list_events(&events) {
+        let row = (summary.id, summary.name, summary.parameter_count).to_py_object(py);
+        result.append(py, row.into_object());
+    }
+
+    Ok(result)
+}
+
+fn schema_search(py: Python, xml: &str, pattern: &str) -> PyResult<PyList> {
+    let events = load_schema(py, xml)?;
+    let result = PyList::new(py, &[]);
+
+    for found in schema::search_parameters(&events, pattern) {
+        let row = (found.parameter, found.event_id, found.event_name).to_py_object(py);
+        result.append(py, row.into_object());
+    }
+
+    Ok(result)
+}
+
+fn schema_validate(py: Python, xml: &str) -> PyResult<PyList> {
+    let result = PyList::new(py, &[]);
+
+    for diagnostic in xml_parser::validate_xml(xml) {
+        let row = (diagnostic.line, diagnostic.column, diagnostic.message).to_py_object(py);
+        result.append(py, row.into_object());
+    }
+
+    Ok(result)
+}
+
+fn load_schema(py: Python, xml: &str) -> PyResult<HashMap<u16, xml_parser::Event>> {
+    xml_parser::parse_xml(xml).map_err(|diagnostics| schema_error(py, xml, diagnostics))
+}
+
+fn schema_error(py: Python, xml: &str, diagnostics: Vec<xml_parser::Diagnostic>) -> PyErr {
+    let messages: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| format!("{}:{}", xml, diagnostic))
+        .collect();
+    PyErr::new::<exc::ValueError, _>(py, messages.join("\n"))
+}
@@ -1,16 +1,63 @@
-use ctr_analyzer::summarize_trace;
+use columnar::ParquetWriter;
+use compare::{compare, TraceSet};
+use ctr_analyzer::{summarize_trace, Summary};
+use distance::{distance_histograms, write_histograms_csv};
 use formatter::format_summary;
-use printer::{print_summary, print_trace_by_ueref, print_trace_in_row};
+use handover::analyze_handovers;
+use kpi::{accessibility, retainability};
+use ladder::{build_ladder, render_mermaid, render_plantuml, render_svg, render_text};
+use neighbour::neighbour_relations;
+use pcap_writer::{file_day_start, PcapWriter};
+use printer::{
+    print_comparison, print_distances, print_dropped, print_handovers, print_kpis,
+    print_measurements, print_neighbours, print_schema, print_schema_diff, print_series,
+    print_sessions, print_summary, print_trace_by_ueref, print_trace_in_row,
+    print_unclassified_causes,
+};
+use schema_diff::diff_schemas;
+use serde::Serialize;
+use session::{build_sessions, sessions_with_ueref};
+use sqlite_writer::SqliteWriter;
+use std::collections::HashMap;
 use std::env;
+use std::io::{self, Write};
 use std::process;
+use timeseries::{time_series, write_series_csv};
+use trace_reader::TraceReader;
+use units::measurement_statistics;
+use writer::{create_csv_writer, create_output, EventWriter, JsonLinesWriter};
+use xml_parser::{Diagnostic, Event};
 
+pub mod columnar;
+pub mod compare;
 pub mod config;
 pub mod converter;
 pub mod ctr_analyzer;
+pub mod distance;
 pub mod formatter;
+pub mod handover;
+pub mod kpi;
+pub mod l3;
+pub mod ladder;
+pub mod logger;
+pub mod nas;
+pub mod neighbour;
 pub mod parser;
+pub mod pcap_writer;
+pub mod per;
 pub mod printer;
+pub mod rrc;
+pub mod s1ap;
+pub mod schema;
+pub mod schema_diff;
+pub mod session;
+pub mod sqlite_writer;
+pub mod stats;
+pub mod timeseries;
 pub mod trace_reader;
+pub mod units;
+pub mod writer;
+pub mod x2ap;
 pub mod xml_parser;
 
 fn main() {
@@ -18,19 +65,290 @@ fn main() {
         eprintln!("problem when parsing arguments: {}", err);
         process::exit(1);
     });
+    logger::init(config.log_level);
 
-    let mut parser = trace_reader::TraceReader::new(&config);
-    parser.read_trace();
+    match &config.output {
+        config::Processing::Schema(query) => {
+            print_schema(&load_schema(&config.xml), query);
+            return;
+        }
+        config::Processing::SchemaDiff { xml, json } => {
+            let old = load_schema(&config.xml);
+            let new = load_schema(xml);
+            print_schema_diff(&diff_schemas(&old, &new), *json);
+            return;
+        }
+        config::Processing::Merge(paths) => {
+            let mut summary = Summary::default();
+            for path in paths {
+                match Summary::from_file(path) {
+                    Ok(other) => summary.merge(other),
+                    Err(err) => {
+                        eprintln!("problem when reading summary: {}", err);
+                        process::exit(1);
+                    }
+                }
+            }
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &summary),
+                None => {
+                    let parameter_types = schema::parameter_types(&load_schema(&config.xml));
+                    print_summary(format_summary(summary, &parameter_types, config.top));
+                }
+            }
+            return;
+        }
+        config::Processing::Compare { before, after } => {
+            let parameter_types = schema::parameter_types(&load_schema(&config.xml));
+            let before = read_trace_set(&config, before);
+            let after = read_trace_set(&config, after);
+            let comparison = compare(before, after, &parameter_types);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &comparison),
+                None => print_comparison(&comparison, config.top),
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    let mut parser = TraceReader::new(&config)
+        .unwrap_or_else(|diagnostics| exit_with_schema_errors(&config.xml, diagnostics));
 
-    match config.output {
+    match &config.output {
         config::Processing::Table => {
+            parser.read_trace();
             print_trace_by_ueref(&mut parser.decoded_trace_events, &config.ueref)
         }
-        config::Processing::Row => print_trace_in_row(&parser.decoded_trace_events),
+        config::Processing::Row => {
+            parser.read_trace();
+            print_trace_in_row(&parser.decoded_trace_events)
+        }
         config::Processing::Summary => {
-            let results = summarize_trace(&parser.decoded_trace_events);
-            let formated_results = format_summary(results);
-            print_summary(formated_results);
+            let parameter_types = schema::parameter_types(parser.events_definition());
+            parser.read_trace();
+            let mut results = summarize_trace(&parser.decoded_trace_events, &config.summary_scope);
+            results.files.push(parser.filename().to_string());
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &results),
+                None => {
+                    let formated_results = format_summary(results, &parameter_types, config.top);
+                    print_summary(formated_results);
+                }
+            }
+        }
+        config::Processing::Csv(layout) => {
+            export(&mut parser, create_csv_writer(layout, &config.output_path))
+        }
+        config::Processing::JsonLines => {
+            let parameter_types = schema::parameter_types(parser.events_definition());
+            let filename = parser.filename().to_string();
+            let writer = create_output(&config.output_path).map(|output| {
+                Box::new(JsonLinesWriter::new(output, &filename, parameter_types))
+                    as Box<dyn EventWriter>
+            });
+            export(&mut parser, writer)
+        }
+        config::Processing::Parquet => {
+            let directory = config
+                .output_path
+                .as_deref()
+                .expect("parquet requires an output directory");
+            let writer = ParquetWriter::new(directory, parser.events_definition())
+                .map(|writer| Box::new(writer) as Box<dyn EventWriter>);
+            export(&mut parser, writer)
+        }
+        config::Processing::Sqlite => {
+            let path = config
+                .output_path
+                .as_deref()
+                .expect("sqlite requires a database file");
+            let writer = SqliteWriter::new(path, parser.events_definition(), parser.filename())
+                .map(|writer| Box::new(writer) as Box<dyn EventWriter>);
+            export(&mut parser, writer)
+        }
+        config::Processing::Pcap => {
+            let day_start = file_day_start(parser.filename());
+            let writer = create_output(&config.output_path)
+                .and_then(|output| PcapWriter::new(output, day_start))
+                .map(|writer| Box::new(writer) as Box<dyn EventWriter>);
+            export(&mut parser, writer)
+        }
+        config::Processing::Sessions => {
+            parser.read_trace();
+            let sessions = build_sessions(&parser.decoded_trace_events);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &sessions),
+                None => print_sessions(&sessions),
+            }
+        }
+        config::Processing::Accessibility => {
+            parser.read_trace();
+            let events = &parser.decoded_trace_events;
+            let kpis = accessibility(events, &build_sessions(events), config.bin_ms);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &kpis),
+                None => print_kpis(&kpis),
+            }
+        }
+        config::Processing::Retainability => {
+            parser.read_trace();
+            let events = &parser.decoded_trace_events;
+            let retainability = retainability(events, &build_sessions(events), config.bin_ms);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &retainability),
+                None => {
+                    print_kpis(&retainability.cells);
+                    print_dropped(&retainability.dropped);
+                    print_unclassified_causes(&retainability.unclassified_causes);
+                }
+            }
+        }
+        config::Processing::Handover => {
+            parser.read_trace();
+            let analysis = analyze_handovers(&parser.decoded_trace_events, config.ping_pong_ms);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &analysis),
+                None => print_handovers(&analysis),
+            }
+        }
+        config::Processing::Measurements => {
+            parser.read_trace();
+            let measurements = measurement_statistics(&parser.decoded_trace_events);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &measurements),
+                None => print_measurements(&measurements),
+            }
+        }
+        config::Processing::Distance { csv } => {
+            parser.read_trace();
+            let histograms = distance_histograms(
+                &parser.decoded_trace_events,
+                config.distance_bin_m,
+                config.radius_m,
+            );
+            if *csv {
+                write_with(&config.output_path, |output| {
+                    write_histograms_csv(output, &histograms)
+                });
+            } else {
+                match &config.output_path {
+                    Some(_) => write_json(&config.output_path, &histograms),
+                    None => print_distances(&histograms, config.radius_m),
+                }
+            }
+        }
+        config::Processing::Neighbours => {
+            parser.read_trace();
+            let relations = neighbour_relations(&parser.decoded_trace_events);
+            match &config.output_path {
+                Some(_) => write_json(&config.output_path, &relations),
+                None => print_neighbours(&relations),
+            }
+        }
+        config::Processing::Series { csv } => {
+            let parameter_types = schema::parameter_types(parser.events_definition());
+            parser.read_trace();
+            let series = time_series(
+                &parser.decoded_trace_events,
+                &parameter_types,
+                config.bin_ms,
+            );
+            if *csv {
+                write_with(&config.output_path, |output| {
+                    write_series_csv(output, &series)
+                });
+            } else {
+                match &config.output_path {
+                    Some(_) => write_json(&config.output_path, &series),
+                    None => print_series(&series),
+                }
+            }
         }
+        config::Processing::Ladder(format) => {
+            parser.read_trace();
+            let events = &parser.decoded_trace_events;
+            let sessions = build_sessions(events);
+            let selected = match config.ueref.as_str() {
+                "all" => sessions.iter().collect(),
+                ueref => sessions_with_ueref(&sessions, ueref),
+            };
+            let ladders: Vec<_> = selected
+                .iter()
+                .map(|session| build_ladder(session, events))
+                .collect();
+            let rendered = match format {
+                config::LadderFormat::Svg => render_svg(&ladders),
+                config::LadderFormat::Text => ladders.iter().map(render_text).collect(),
+                config::LadderFormat::PlantUml => ladders.iter().map(render_plantuml).collect(),
+                config::LadderFormat::Mermaid => ladders.iter().map(render_mermaid).collect(),
+            };
+            write_with(&config.output_path, |output| {
+                output.write_all(rendered.as_bytes())
+            });
+        }
+        config::Processing::Schema(_)
+        | config::Processing::SchemaDiff { .. }
+        | config::Processing::Merge(_)
+        | config::Processing::Compare { .. } => {}
     };
 }
+
+fn export(parser: &mut TraceReader, writer: io::Result<Box<dyn EventWriter>>) {
+    let result = writer.and_then(|mut writer| {
+        parser.read_trace_with(|event| writer.write(&event))?;
+        writer.finish()
+    });
+    if let Err(err) = result {
+        eprintln!("problem when writing output: {}", err);
+        process::exit(1);
+    }
+}
+
+/// Summarises the trace files of one side of a comparison.
+fn read_trace_set(config: &config::Config, files: &[String]) -> TraceSet {
+    let mut set = TraceSet::default();
+    for file in files {
+        let mut parser = TraceReader::open(config, file)
+            .unwrap_or_else(|diagnostics| exit_with_schema_errors(&config.xml, diagnostics));
+        parser.read_trace();
+        set.add(file, &parser.decoded_trace_events, &config.summary_scope);
+    }
+    set
+}
+
+/// Writes `value` as pretty-printed JSON to the output path, or stdout.
+fn write_json<T: Serialize>(path: &Option<String>, value: &T) {
+    write_with(path, |output| {
+        serde_json::to_writer_pretty(output, value)?;
+        Ok(())
+    });
+}
+
+/// Writes to the output path, or stdout, with `write`, and exits if that
+/// fails.
+fn write_with<F>(path: &Option<String>, write: F)
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let result = create_output(path).and_then(|mut output| {
+        write(&mut output)?;
+        output.flush()
+    });
+    if let Err(err) = result {
+        eprintln!("problem when writing output: {}", err);
+        process::exit(1);
+    }
+}
+
+fn load_schema(xml: &str) -> HashMap<u16, Event> {
+    xml_parser::parse_xml(xml)
+        .unwrap_or_else(|diagnostics| exit_with_schema_errors(xml, diagnostics))
+}
+
+fn exit_with_schema_errors(xml: &str, diagnostics: Vec<Diagnostic>) -> ! {
+    for diagnostic in diagnostics {
+        eprintln!("{}:{}", xml, diagnostic);
+    }
+    process::exit(1);
+}
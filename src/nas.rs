@@ -0,0 +1,614 @@
+use super::l3::L3Field;
+use super::s1ap::tbcd_plmn;
+
+const EMM: u8 = 0x7;
+const ESM: u8 = 0x2;
+const SERVICE_REQUEST_HEADER: u8 = 0xc;
+
+const SECURITY_HEADER_TYPES: [&str; 5] = [
+    "plain",
+    "integrity protected",
+    "integrity protected and ciphered",
+    "integrity protected with new EPS security context",
+    "integrity protected and ciphered with new EPS security context",
+];
+
+const EMM_MESSAGES: [(u8, &str); 30] = [
+    (0x41, "attachRequest"),
+    (0x42, "attachAccept"),
+    (0x43, "attachComplete"),
+    (0x44, "attachReject"),
+    (0x45, "detachRequest"),
+    (0x46, "detachAccept"),
+    (0x48, "trackingAreaUpdateRequest"),
+    (0x49, "trackingAreaUpdateAccept"),
+    (0x4a, "trackingAreaUpdateComplete"),
+    (0x4b, "trackingAreaUpdateReject"),
+    (0x4c, "extendedServiceRequest"),
+    (0x4e, "serviceReject"),
+    (0x50, "gutiReallocationCommand"),
+    (0x51, "gutiReallocationComplete"),
+    (0x52, "authenticationRequest"),
+    (0x53, "authenticationResponse"),
+    (0x54, "authenticationReject"),
+    (0x55, "identityRequest"),
+    (0x56, "identityResponse"),
+    (0x5c, "authenticationFailure"),
+    (0x5d, "securityModeCommand"),
+    (0x5e, "securityModeComplete"),
+    (0x5f, "securityModeReject"),
+    (0x60, "emmStatus"),
+    (0x61, "emmInformation"),
+    (0x62, "downlinkNasTransport"),
+    (0x63, "uplinkNasTransport"),
+    (0x64, "csServiceNotification"),
+    (0x68, "downlinkGenericNasTransport"),
+    (0x69, "uplinkGenericNasTransport"),
+];
+
+const ESM_MESSAGES: [(u8, &str); 25] = [
+    (0xc1, "activateDefaultEpsBearerContextRequest"),
+    (0xc2, "activateDefaultEpsBearerContextAccept"),
+    (0xc3, "activateDefaultEpsBearerContextReject"),
+    (0xc5, "activateDedicatedEpsBearerContextRequest"),
+    (0xc6, "activateDedicatedEpsBearerContextAccept"),
+    (0xc7, "activateDedicatedEpsBearerContextReject"),
+    (0xc9, "modifyEpsBearerContextRequest"),
+    (0xca, "modifyEpsBearerContextAccept"),
+    (0xcb, "modifyEpsBearerContextReject"),
+    (0xcd, "deactivateEpsBearerContextRequest"),
+    (0xce, "deactivateEpsBearerContextAccept"),
+    (0xd0, "pdnConnectivityRequest"),
+    (0xd1, "pdnConnectivityReject"),
+    (0xd2, "pdnDisconnectRequest"),
+    (0xd3, "pdnDisconnectReject"),
+    (0xd4, "bearerResourceAllocationRequest"),
+    (0xd5, "bearerResourceAllocationReject"),
+    (0xd6, "bearerResourceModificationRequest"),
+    (0xd7, "bearerResourceModificationReject"),
+    (0xd9, "esmInformationRequest"),
+    (0xda, "esmInformationResponse"),
+    (0xdb, "notification"),
+    (0xe8, "esmStatus"),
+    (0xe9, "remoteUeReport"),
+    (0xea, "remoteUeReportResponse"),
+];
+
+/// ESM messages whose only mandatory IE after the header is the ESM cause.
+const ESM_CAUSE_MESSAGES: [u8; 9] = [0xc3, 0xc7, 0xcb, 0xcd, 0xd1, 0xd3, 0xd5, 0xd7, 0xe8];
+
+const EMM_CAUSES: [(u8, &str); 39] = [
+    (2, "IMSI unknown in HSS"),
+    (3, "Illegal UE"),
+    (5, "IMEI not accepted"),
+    (6, "Illegal ME"),
+    (7, "EPS services not allowed"),
+    (8, "EPS services and non-EPS services not allowed"),
+    (9, "UE identity cannot be derived by the network"),
+    (10, "Implicitly detached"),
+    (11, "PLMN not allowed"),
+    (12, "Tracking Area not allowed"),
+    (13, "Roaming not allowed in this tracking area"),
+    (14, "EPS services not allowed in this PLMN"),
+    (15, "No Suitable Cells In tracking area"),
+    (16, "MSC temporarily not reachable"),
+    (17, "Network failure"),
+    (18, "CS domain not available"),
+    (19, "ESM failure"),
+    (20, "MAC failure"),
+    (21, "Synch failure"),
+    (22, "Congestion"),
+    (23, "UE security capabilities mismatch"),
+    (24, "Security mode rejected, unspecified"),
+    (25, "Not authorized for this CSG"),
+    (26, "Non-EPS authentication unacceptable"),
+    (31, "Redirection to 5GCN required"),
+    (35, "Requested service option not authorized in this PLMN"),
+    (39, "CS service temporarily not available"),
+    (40, "No EPS bearer context activated"),
+    (42, "Severe network failure"),
+    (72, "Non-3GPP access to 5GCN not allowed"),
+    (78, "PLMN not allowed to operate at the present UE location"),
+    (95, "Semantically incorrect message"),
+    (96, "Invalid mandatory information"),
+    (97, "Message type non-existent or not implemented"),
+    (98, "Message type not compatible with the protocol state"),
+    (99, "Information element non-existent or not implemented"),
+    (100, "Conditional IE error"),
+    (101, "Message not compatible with the protocol state"),
+    (111, "Protocol error, unspecified"),
+];
+
+const ESM_CAUSES: [(u8, &str); 46] = [
+    (8, "Operator Determined Barring"),
+    (26, "Insufficient resources"),
+    (27, "Missing or unknown APN"),
+    (28, "Unknown PDN type"),
+    (29, "User authentication failed"),
+    (30, "Request rejected by Serving GW or PDN GW"),
+    (31, "Request rejected, unspecified"),
+    (32, "Service option not supported"),
+    (33, "Requested service option not subscribed"),
+    (34, "Service option temporarily out of order"),
+    (35, "PTI already in use"),
+    (36, "Regular deactivation"),
+    (37, "EPS QoS not accepted"),
+    (38, "Network failure"),
+    (39, "Reactivation requested"),
+    (41, "Semantic error in the TFT operation"),
+    (42, "Syntactical error in the TFT operation"),
+    (43, "Invalid EPS bearer identity"),
+    (44, "Semantic errors in packet filter(s)"),
+    (45, "Syntactical errors in packet filter(s)"),
+    (47, "PTI mismatch"),
+    (49, "Last PDN disconnection not allowed"),
+    (50, "PDN type IPv4 only allowed"),
+    (51, "PDN type IPv6 only allowed"),
+    (52, "Single address bearers only allowed"),
+    (53, "ESM information not received"),
+    (54, "PDN connection does not exist"),
+    (55, "Multiple PDN connections for a given APN not allowed"),
+    (56, "Collision with network initiated request"),
+    (57, "PDN type IPv4v6 only allowed"),
+    (58, "PDN type non IP only allowed"),
+    (59, "Unsupported QCI value"),
+    (60, "Bearer handling not supported"),
+    (65, "Maximum number of EPS bearers reached"),
+    (
+        66,
+        "Requested APN not supported in current RAT and PLMN combination",
+    ),
+    (81, "Invalid PTI value"),
+    (95, "Semantically incorrect message"),
+    (96, "Invalid mandatory information"),
+    (97, "Message type non-existent or not implemented"),
+    (98, "Message type not compatible with the protocol state"),
+    (99, "Information element non-existent or not implemented"),
+    (100, "Conditional IE error"),
+    (101, "Message not compatible with the protocol state"),
+    (111, "Protocol error, unspecified"),
+    (
+        112,
+        "APN restriction value incompatible with active EPS bearer context",
+    ),
+    (113, "Multiple accesses to a PDN connection not allowed"),
+];
+
+const ATTACH_TYPES: [(u8, &str); 3] = [
+    (1, "EPS attach"),
+    (2, "combined EPS/IMSI attach"),
+    (6, "EPS emergency attach"),
+];
+const ATTACH_RESULTS: [(u8, &str); 2] = [(1, "EPS only"), (2, "combined EPS/IMSI attach")];
+const UPDATE_TYPES: [(u8, &str); 4] = [
+    (0, "TA updating"),
+    (1, "combined TA/LA updating"),
+    (2, "combined TA/LA updating with IMSI attach"),
+    (3, "periodic updating"),
+];
+const UPDATE_RESULTS: [(u8, &str); 4] = [
+    (0, "TA updated"),
+    (1, "combined TA/LA updated"),
+    (4, "TA updated and ISR activated"),
+    (5, "combined TA/LA updated and ISR activated"),
+];
+const SERVICE_TYPES: [(u8, &str); 4] = [
+    (0, "mobile originating CS fallback or 1xCS fallback"),
+    (1, "mobile terminating CS fallback or 1xCS fallback"),
+    (2, "mobile originating CS fallback emergency call"),
+    (8, "packet services via S1"),
+];
+const REQUEST_TYPES: [(u8, &str); 3] = [(1, "initial request"), (2, "handover"), (4, "emergency")];
+const PDN_TYPES: [(u8, &str); 4] = [(1, "IPv4"), (2, "IPv6"), (3, "IPv4v6"), (5, "non IP")];
+const IDENTITY_TYPES: [(u8, &str); 4] = [(1, "IMSI"), (2, "IMEI"), (3, "IMEISV"), (4, "TMSI")];
+
+const ESM_MESSAGE_CONTAINER: u8 = 0x78;
+const EMM_CAUSE: u8 = 0x53;
+
+/// A NAS container: the raw bytes as value, with the decoded message below.
+pub fn nas_field(name: &str, contents: &[u8]) -> L3Field {
+    let mut field = L3Field::leaf(name, hex::encode(contents));
+    field.push(decode(contents));
+    field
+}
+
+/// Decodes an EPS NAS message (3GPP TS 24.301). Only plain messages, on their
+/// own or inside an integrity protected one, can be decoded; ciphered
+/// messages are flagged with a `cipheredMessage` field.
+pub fn decode(contents: &[u8]) -> L3Field {
+    let mut root = L3Field::new("NAS-EPS");
+    if decode_message(&mut Cursor::new(contents), &mut root).is_none() {
+        root.push(L3Field::leaf("error", "message truncated"));
+    }
+    root
+}
+
+/// Position in a NAS message, with the type 4 and 6 IE readers.
+struct Cursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Cursor<'a> {
+        Cursor { data, position: 0 }
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let byte = *self.data.get(self.position)?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.position.min(self.data.len())..];
+        self.position = self.data.len();
+        rest
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).copied()
+    }
+
+    /// Value of a length-value IE.
+    fn lv(&mut self) -> Option<&'a [u8]> {
+        let length = self.byte()? as usize;
+        self.bytes(length)
+    }
+
+    /// Value of a length-value IE with a two octet length.
+    fn lv_e(&mut self) -> Option<&'a [u8]> {
+        let length = u16::from_be_bytes([self.byte()?, self.byte()?]) as usize;
+        self.bytes(length)
+    }
+}
+
+fn lookup(table: &[(u8, &str)], value: u8) -> String {
+    match table.iter().find(|(known, _)| *known == value) {
+        Some((_, name)) => name.to_string(),
+        None => format!("unknown ({})", value),
+    }
+}
+
+fn cause_value(table: &[(u8, &str)], value: u8) -> String {
+    match table.iter().find(|(known, _)| *known == value) {
+        Some((_, name)) => format!("{} ({})", value, name),
+        None => value.to_string(),
+    }
+}
+
+fn decode_message(cursor: &mut Cursor, parent: &mut L3Field) -> Option<()> {
+    let first = cursor.byte()?;
+    let discriminator = first & 0x0f;
+    match discriminator {
+        EMM => decode_emm(cursor, parent, first >> 4),
+        ESM => decode_esm(cursor, parent, first >> 4),
+        _ => {
+            parent.push(L3Field::leaf("protocolDiscriminator", discriminator));
+            Some(())
+        }
+    }
+}
+
+fn decode_emm(cursor: &mut Cursor, parent: &mut L3Field, security_header: u8) -> Option<()> {
+    if security_header == SERVICE_REQUEST_HEADER {
+        let message = parent.child("serviceRequest");
+        let ksi_and_sequence = cursor.byte()?;
+        message.push(L3Field::leaf("nasKeySetIdentifier", ksi_and_sequence >> 5));
+        message.push(L3Field::leaf("sequenceNumber", ksi_and_sequence & 0x1f));
+        message.push(L3Field::leaf("shortMac", hex::encode(cursor.bytes(2)?)));
+        return Some(());
+    }
+
+    let header_name = SECURITY_HEADER_TYPES
+        .get(security_header as usize)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("reserved ({})", security_header));
+    parent.push(L3Field::leaf("securityHeaderType", header_name));
+    match security_header {
+        0 => {}
+        1 | 3 => {
+            parent.push(L3Field::leaf(
+                "messageAuthenticationCode",
+                hex::encode(cursor.bytes(4)?),
+            ));
+            parent.push(L3Field::leaf("sequenceNumber", cursor.byte()?));
+            return decode_message(cursor, parent);
+        }
+        2 | 4 => {
+            parent.push(L3Field::leaf(
+                "messageAuthenticationCode",
+                hex::encode(cursor.bytes(4)?),
+            ));
+            parent.push(L3Field::leaf("sequenceNumber", cursor.byte()?));
+            parent.push(L3Field::new("cipheredMessage"));
+            return Some(());
+        }
+        _ => return Some(()),
+    }
+
+    let message_type = cursor.byte()?;
+    let name = match EMM_MESSAGES
+        .iter()
+        .find(|(known, _)| *known == message_type)
+    {
+        Some((_, name)) => name.to_string(),
+        None => format!("messageType {:#04x}", message_type),
+    };
+    let message = parent.child(&name);
+    match message_type {
+        0x41 => {
+            let types = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "epsAttachType",
+                lookup(&ATTACH_TYPES, types & 0x07),
+            ));
+            message.push(L3Field::leaf("nasKeySetIdentifier", (types >> 4) & 0x07));
+            message.push(mobile_identity("epsMobileIdentity", cursor.lv()?));
+            cursor.lv()?;
+            esm_container(cursor.lv_e()?, message);
+        }
+        0x42 => {
+            let result = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "epsAttachResult",
+                lookup(&ATTACH_RESULTS, result & 0x07),
+            ));
+            message.push(L3Field::leaf("t3412", cursor.byte()?));
+            cursor.lv()?;
+            esm_container(cursor.lv_e()?, message);
+        }
+        0x43 => esm_container(cursor.lv_e()?, message),
+        0x44 => {
+            message.push(emm_cause(cursor.byte()?));
+            if cursor.byte() == Some(ESM_MESSAGE_CONTAINER) {
+                esm_container(cursor.lv_e()?, message);
+            }
+        }
+        0x45 => {
+            let types = cursor.byte()?;
+            message.push(L3Field::leaf("typeOfDetach", types & 0x07));
+            message.push(L3Field::leaf("switchOff", types & 0x08 != 0));
+            // Only the network sends a detach request with an EMM cause;
+            // the one from the UE carries a mobile identity instead.
+            if cursor.peek() == Some(EMM_CAUSE) {
+                cursor.byte()?;
+                message.push(emm_cause(cursor.byte()?));
+            } else if cursor.peek().is_some() {
+                message.push(mobile_identity("epsMobileIdentity", cursor.lv()?));
+            }
+        }
+        0x48 => {
+            let types = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "epsUpdateType",
+                lookup(&UPDATE_TYPES, types & 0x07),
+            ));
+            message.push(L3Field::leaf("activeFlag", types & 0x08 != 0));
+            message.push(mobile_identity("oldGuti", cursor.lv()?));
+        }
+        0x49 => {
+            let result = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "epsUpdateResult",
+                lookup(&UPDATE_RESULTS, result & 0x07),
+            ));
+        }
+        0x4b | 0x4e | 0x5c | 0x5f | 0x60 => message.push(emm_cause(cursor.byte()?)),
+        0x4c => {
+            let types = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "serviceType",
+                lookup(&SERVICE_TYPES, types & 0x0f),
+            ));
+            message.push(mobile_identity("mTmsi", cursor.lv()?));
+        }
+        0x55 => {
+            let identity = cursor.byte()? & 0x07;
+            message.push(L3Field::leaf(
+                "identityType",
+                lookup(&IDENTITY_TYPES, identity),
+            ));
+        }
+        0x56 => message.push(mobile_identity("mobileIdentity", cursor.lv()?)),
+        0x5d => {
+            let algorithms = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "typeOfCipheringAlgorithm",
+                format!("EEA{}", (algorithms >> 4) & 0x07),
+            ));
+            message.push(L3Field::leaf(
+                "typeOfIntegrityProtectionAlgorithm",
+                format!("EIA{}", algorithms & 0x07),
+            ));
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+/// Decodes the ESM message carried in an EMM message.
+fn esm_container(contents: &[u8], message: &mut L3Field) {
+    let container = message.child("esmMessageContainer");
+    if decode_message(&mut Cursor::new(contents), container).is_none() {
+        container.push(L3Field::leaf("error", "message truncated"));
+    }
+}
+
+fn decode_esm(cursor: &mut Cursor, parent: &mut L3Field, bearer: u8) -> Option<()> {
+    let transaction = cursor.byte()?;
+    let message_type = cursor.byte()?;
+    let name = match ESM_MESSAGES
+        .iter()
+        .find(|(known, _)| *known == message_type)
+    {
+        Some((_, name)) => name.to_string(),
+        None => format!("messageType {:#04x}", message_type),
+    };
+    let message = parent.child(&name);
+    message.push(L3Field::leaf("epsBearerIdentity", bearer));
+    message.push(L3Field::leaf("procedureTransactionIdentity", transaction));
+
+    if ESM_CAUSE_MESSAGES.contains(&message_type) {
+        message.push(L3Field::leaf(
+            "esmCause",
+            cause_value(&ESM_CAUSES, cursor.byte()?),
+        ));
+        return Some(());
+    }
+    match message_type {
+        0xc1 => {
+            let qos = cursor.lv()?;
+            message.push(L3Field::leaf("qci", *qos.first()?));
+            message.push(L3Field::leaf("accessPointName", apn(cursor.lv()?)));
+            message.push(pdn_address(cursor.lv()?)?);
+        }
+        0xc5 => {
+            message.push(L3Field::leaf(
+                "linkedEpsBearerIdentity",
+                cursor.byte()? & 0x0f,
+            ));
+            let qos = cursor.lv()?;
+            message.push(L3Field::leaf("qci", *qos.first()?));
+        }
+        0xd0 => {
+            let types = cursor.byte()?;
+            message.push(L3Field::leaf(
+                "requestType",
+                lookup(&REQUEST_TYPES, types & 0x07),
+            ));
+            message.push(L3Field::leaf(
+                "pdnType",
+                lookup(&PDN_TYPES, (types >> 4) & 0x07),
+            ));
+        }
+        0xd2 => {
+            message.push(L3Field::leaf(
+                "linkedEpsBearerIdentity",
+                cursor.byte()? & 0x0f,
+            ));
+        }
+        _ => {}
+    }
+    cursor.rest();
+    Some(())
+}
+
+fn emm_cause(value: u8) -> L3Field {
+    L3Field::leaf("emmCause", cause_value(&EMM_CAUSES, value))
+}
+
+/// EPS mobile identity: IMSI or IMEI digits, a GUTI or an M-TMSI.
+fn mobile_identity(name: &str, value: &[u8]) -> L3Field {
+    let identity_type = value.first().map(|first| first & 0x07).unwrap_or(0);
+    match identity_type {
+        1 | 3 => L3Field::leaf(name, identity_digits(value)),
+        6 if value.len() >= 11 => {
+            let mut field = L3Field::new(name);
+            field.push(L3Field::leaf("plmn", tbcd_plmn(&value[1..4])));
+            field.push(L3Field::leaf("mmeGroupId", hex::encode(&value[4..6])));
+            field.push(L3Field::leaf("mmeCode", hex::encode(&value[6..7])));
+            field.push(L3Field::leaf("mTmsi", hex::encode(&value[7..11])));
+            field
+        }
+        4 if value.len() >= 5 => L3Field::leaf(name, hex::encode(&value[1..5])),
+        _ => L3Field::leaf(name, hex::encode(value)),
+    }
+}
+
+/// Digits of an IMSI or IMEI, with the filler of an even count left out.
+fn identity_digits(value: &[u8]) -> String {
+    let mut digits = String::new();
+    for (index, octet) in value.iter().enumerate() {
+        if index > 0 {
+            digits.push(char::from(b'0' + (octet & 0x0f).min(9)));
+        }
+        if octet >> 4 != 0x0f {
+            digits.push(char::from(b'0' + (octet >> 4).min(9)));
+        }
+    }
+    digits
+}
+
+/// Access point name from its length-prefixed labels.
+fn apn(value: &[u8]) -> String {
+    let mut labels = Vec::new();
+    let mut rest = value;
+    while let Some((&length, tail)) = rest.split_first() {
+        let length = (length as usize).min(tail.len());
+        labels.push(String::from_utf8_lossy(&tail[..length]).to_string());
+        rest = &tail[length..];
+    }
+    labels.join(".")
+}
+
+fn pdn_address(value: &[u8]) -> Option<L3Field> {
+    let mut field = L3Field::new("pdnAddress");
+    let pdn_type = value.first()? & 0x07;
+    field.push(L3Field::leaf("pdnType", lookup(&PDN_TYPES, pdn_type)));
+    let ipv4 = match pdn_type {
+        1 => value.get(1..5),
+        3 => value.get(9..13),
+        _ => None,
+    };
+    if let Some(address) = ipv4 {
+        let octets: Vec<String> = address.iter().map(|octet| octet.to_string()).collect();
+        field.push(L3Field::leaf("ipv4Address", octets.join(".")));
+    }
+    Some(field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_attach_reject() {
+        let message = decode(&[0x07, 0x44, 0x13]);
+        assert_eq!(
+            message.flatten(),
+            vec![
+                (
+                    "NAS-EPS.securityHeaderType".to_string(),
+                    "plain".to_string()
+                ),
+                (
+                    "NAS-EPS.attachReject.emmCause".to_string(),
+                    "19 (ESM failure)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_attach_request() {
+        // Integrity protected attach request with an IMSI, carrying a PDN
+        // connectivity request
+        let message = decode(&[
+            0x17, 0x01, 0x02, 0x03, 0x04, 0x05, 0x07, 0x41, 0x71, 0x08, 0x29, 0x40, 0x01, 0x01,
+            0x23, 0x45, 0x67, 0x89, 0x02, 0xe0, 0xe0, 0x00, 0x04, 0x02, 0x01, 0xd0, 0x11,
+        ]);
+        assert_eq!(
+            message.find("epsMobileIdentity").unwrap().value.as_deref(),
+            Some("204101032547698")
+        );
+        assert_eq!(
+            message.find("epsAttachType").unwrap().value.as_deref(),
+            Some("EPS attach")
+        );
+        let container = message.find("esmMessageContainer").unwrap();
+        assert_eq!(
+            container.find("requestType").unwrap().value.as_deref(),
+            Some("initial request")
+        );
+
+        let ciphered = decode(&[0x27, 0x01, 0x02, 0x03, 0x04, 0x05, 0xaa, 0xbb]);
+        assert!(ciphered.find("cipheredMessage").is_some());
+    }
+}
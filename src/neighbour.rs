@@ -0,0 +1,292 @@
+use super::handover::target_cell;
+use super::kpi::event_cells;
+use super::l3::{decode_event, L3Field};
+use super::session::build_sessions;
+use super::trace_reader::TraceEvent;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+const UNKNOWN_CELL: &str = "unknown";
+
+/// Outgoing handover preparations, the attempts towards a neighbour.
+const HANDOVER_PREPARATIONS: [&str; 2] = [
+    "INTERNAL_PROC_HO_PREP_X2_OUT",
+    "INTERNAL_PROC_HO_PREP_S1_OUT",
+];
+
+/// A neighbour PCI as reported by the UEs of one serving cell on one EARFCN,
+/// which is left out when the measurement configuration of the reports is
+/// not in the trace. A neighbour reported often without handover attempts
+/// hints at a missing neighbour relation, and handovers to several cells
+/// for one PCI at PCI confusion.
+#[derive(Debug, Default, Serialize)]
+pub struct NeighbourRelation {
+    pub serving_cell: String,
+    pub earfcn: Option<String>,
+    pub pci: String,
+    pub reports: u64,
+    pub mean_rsrp_delta: f64,
+    pub handover_attempts: u64,
+    pub target_cells: Vec<String>,
+}
+
+impl NeighbourRelation {
+    /// Whether the PCI was handed over to more than one target cell from
+    /// the same serving cell and EARFCN.
+    pub fn pci_confusion(&self) -> bool {
+        self.target_cells.len() > 1
+    }
+}
+
+/// Neighbour statistics per serving cell from the decoded RRC measurement
+/// reports: how often each neighbour PCI is reported and its mean RSRP over
+/// the serving cell, in dB. The EARFCN of a report is the carrier frequency
+/// of the measurement object its `measId` was configured with. A handover preparation counts as an attempt
+/// towards the strongest neighbour of the measurement report that triggered
+/// it, the UE's last one; preparations without a new report in between are
+/// not attributed to any neighbour.
+pub fn neighbour_relations(events: &[TraceEvent]) -> Vec<NeighbourRelation> {
+    let sessions = build_sessions(events);
+    let cells = event_cells(events, &sessions);
+    let mut relations: BTreeMap<RelationKey, NeighbourRelation> = BTreeMap::new();
+    let mut deltas: BTreeMap<RelationKey, i64> = BTreeMap::new();
+
+    for session in &sessions {
+        // The strongest neighbour of the UE's last measurement report, until
+        // a handover preparation is attributed to it.
+        let mut strongest: Option<(RelationKey, i64)> = None;
+        let mut configuration = MeasurementConfiguration::default();
+        for &index in &session.events {
+            let event = &events[index];
+            if HANDOVER_PREPARATIONS.contains(&event.name.as_str()) {
+                if let Some(relation) = strongest
+                    .take()
+                    .and_then(|(key, _)| relations.get_mut(&key))
+                {
+                    relation.handover_attempts += 1;
+                    let target = target_cell(event, session, events);
+                    if target != UNKNOWN_CELL && !relation.target_cells.contains(&target) {
+                        relation.target_cells.push(target);
+                    }
+                }
+                continue;
+            }
+            let message = match decode_event(event) {
+                Some(message) => message,
+                None => continue,
+            };
+            if let Some(config) = message.find("measConfig") {
+                configuration.apply(config);
+                continue;
+            }
+            let reported = match measured_neighbours(&message) {
+                Some(reported) => reported,
+                None => continue,
+            };
+            let earfcn = configuration.earfcn(&message);
+            strongest = None;
+            for (pci, delta) in reported {
+                let key = (cells[index].to_string(), earfcn.clone(), pci);
+                let relation = relations
+                    .entry(key.clone())
+                    .or_insert_with(|| NeighbourRelation {
+                        serving_cell: key.0.to_string(),
+                        earfcn: key.1.clone(),
+                        pci: key.2.to_string(),
+                        ..Default::default()
+                    });
+                relation.reports += 1;
+                *deltas.entry(key.clone()).or_default() += delta;
+                if strongest.as_ref().is_none_or(|(_, best)| delta > *best) {
+                    strongest = Some((key, delta));
+                }
+            }
+        }
+    }
+
+    relations
+        .into_iter()
+        .map(|(key, mut relation)| {
+            relation.mean_rsrp_delta = deltas[&key] as f64 / relation.reports as f64;
+            relation
+        })
+        .collect()
+}
+
+/// Serving cell, EARFCN and PCI of a neighbour relation.
+type RelationKey = (String, Option<String>, String);
+
+/// The measurement configuration of a UE, built up from the `measConfig` of
+/// the reconfigurations sent to it.
+#[derive(Default)]
+struct MeasurementConfiguration {
+    /// Carrier frequency of each E-UTRA measurement object.
+    carriers: HashMap<String, String>,
+    /// Measurement object of each measurement identity.
+    objects: HashMap<String, String>,
+}
+
+impl MeasurementConfiguration {
+    fn apply(&mut self, config: &L3Field) {
+        for object in values(config, "measObjectToRemoveList", "measObjectId") {
+            self.carriers.remove(&object);
+        }
+        for object in children(config, "measObjectToAddModList") {
+            if let Some(id) = value(object, "measObjectId") {
+                match value(object, "carrierFreq") {
+                    Some(carrier) => self.carriers.insert(id, carrier),
+                    None => self.carriers.remove(&id),
+                };
+            }
+        }
+        for identity in values(config, "measIdToRemoveList", "measId") {
+            self.objects.remove(&identity);
+        }
+        for identity in children(config, "measIdToAddModList") {
+            if let (Some(id), Some(object)) =
+                (value(identity, "measId"), value(identity, "measObjectId"))
+            {
+                self.objects.insert(id, object);
+            }
+        }
+    }
+
+    /// EARFCN of a measurement report, if its `measId` is configured.
+    fn earfcn(&self, report: &L3Field) -> Option<String> {
+        let object = self.objects.get(&value(report, "measId")?)?;
+        self.carriers.get(object).cloned()
+    }
+}
+
+fn children<'a>(config: &'a L3Field, list: &str) -> &'a [L3Field] {
+    config
+        .find(list)
+        .map(|list| list.children.as_slice())
+        .unwrap_or_default()
+}
+
+fn values(config: &L3Field, list: &str, name: &str) -> Vec<String> {
+    children(config, list)
+        .iter()
+        .filter_map(|field| value(field, name))
+        .collect()
+}
+
+fn value(field: &L3Field, name: &str) -> Option<String> {
+    field.find(name)?.value.clone()
+}
+
+/// The PCI and RSRP over the serving cell of the E-UTRA neighbours in a
+/// decoded measurement report. The RSRP ranges step by 1 dB, so the
+/// difference of the reported values is the delta in dB.
+fn measured_neighbours(message: &L3Field) -> Option<Vec<(String, i64)>> {
+    let results = message.find("measResults")?;
+    let serving: i64 = results
+        .find("measResultPCell")?
+        .find("rsrpResult")?
+        .value
+        .as_deref()?
+        .parse()
+        .ok()?;
+    let neighbours = match results.find("measResultListEUTRA") {
+        Some(list) => list
+            .children
+            .iter()
+            .filter_map(|cell| {
+                let pci = cell.find("physCellId")?.value.clone()?;
+                let rsrp: i64 = cell.find("rsrpResult")?.value.as_deref()?.parse().ok()?;
+                Some((pci, rsrp - serving))
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    Some(neighbours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::l3::{DIRECTION_SENT, L3_MESSAGE_CONTENTS, MESSAGE_DIRECTION};
+    use crate::session::{GLOBAL_CELL_ID, RAC_UE_REF};
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_neighbour_relations() {
+        // measId 1, PCell RSRP 50, neighbour PCI 100 with RSRP 40
+        let report = (L3_MESSAGE_CONTENTS, "08103250019128");
+        let measurement = |timestamp, ueref| {
+            event(
+                "RRC_MEASUREMENT_REPORT",
+                timestamp,
+                &[(RAC_UE_REF, ueref), (GLOBAL_CELL_ID, "25601"), report],
+            )
+        };
+        // measId 1 measuring measObject 2 on EARFCN 1850 or 3050
+        let earfcn_1850 = "201015000200039d5a0002802419700020";
+        let earfcn_3050 = "20101500020005f55a0002802419700020";
+        let reconfiguration = |timestamp, ueref, contents| {
+            event(
+                "RRC_RRC_CONNECTION_RECONFIGURATION",
+                timestamp,
+                &[
+                    (RAC_UE_REF, ueref),
+                    (MESSAGE_DIRECTION, DIRECTION_SENT),
+                    (L3_MESSAGE_CONTENTS, contents),
+                ],
+            )
+        };
+        let preparation = |timestamp, ueref, target| {
+            event(
+                "INTERNAL_PROC_HO_PREP_X2_OUT",
+                timestamp,
+                &[
+                    (RAC_UE_REF, ueref),
+                    ("EVENT_PARAM_HO_TARGET_CELL_ID", target),
+                ],
+            )
+        };
+        let events = vec![
+            reconfiguration(50, "1", earfcn_1850),
+            measurement(100, "1"),
+            preparation(200, "1", "51201"),
+            // A second preparation without a new report is not attributed.
+            preparation(300, "1", "76801"),
+            reconfiguration(350, "2", earfcn_3050),
+            measurement(400, "2"),
+            preparation(500, "2", "76801"),
+        ];
+        let relations = neighbour_relations(&events);
+
+        assert_eq!(relations.len(), 2);
+        let relation = &relations[0];
+        assert_eq!(
+            (
+                relation.serving_cell.as_str(),
+                relation.earfcn.as_deref(),
+                relation.pci.as_str()
+            ),
+            ("25601", Some("1850"), "100")
+        );
+        assert_eq!(relation.reports, 1);
+        assert_eq!(relation.mean_rsrp_delta, -10.0);
+        assert_eq!(relation.handover_attempts, 1);
+        assert_eq!(relation.target_cells, vec!["51201"]);
+        assert!(!relation.pci_confusion());
+        // The same PCI on another EARFCN is another neighbour.
+        assert_eq!(relations[1].target_cells, vec!["76801"]);
+        assert!(!relations[1].pci_confusion());
+
+        let mut confused = events;
+        confused.push(reconfiguration(550, "3", earfcn_1850));
+        confused.push(measurement(600, "3"));
+        confused.push(preparation(700, "3", "76801"));
+        let relations = neighbour_relations(&confused);
+        assert_eq!(relations[0].handover_attempts, 2);
+        assert_eq!(relations[0].target_cells, vec!["51201", "76801"]);
+        assert!(relations[0].pci_confusion());
+
+        // Without the measurement configuration the EARFCN is unknown.
+        let relations = neighbour_relations(&[measurement(100, "4")]);
+        assert_eq!(relations[0].earfcn, None);
+    }
+}
@@ -0,0 +1,182 @@
+use super::l3::l3_message;
+use super::trace_reader::TraceEvent;
+use super::writer::EventWriter;
+use std::io::{self, Write};
+use std::path::Path;
+
+const PCAP_MAGIC: u32 = 0xa1b2c3d4;
+const SNAPLEN: u32 = 65535;
+const LINKTYPE_WIRESHARK_UPPER_PDU: u32 = 252;
+const EXP_PDU_TAG_END_OF_OPT: u16 = 0;
+const EXP_PDU_TAG_PROTO_NAME: u16 = 12;
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// Writes the L3 messages carried by events as a pcap file using Wireshark's
+/// exported PDU link type, so each packet is handed straight to the RRC,
+/// S1AP or X2AP dissector. Events without an L3 message are skipped.
+pub struct PcapWriter<W: Write> {
+    output: W,
+    day_start: u64,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// `day_start` is the trace date in seconds since the epoch; event
+    /// timestamps are only time of day.
+    pub fn new(mut output: W, day_start: u64) -> io::Result<Self> {
+        output.write_all(&PCAP_MAGIC.to_le_bytes())?;
+        output.write_all(&2u16.to_le_bytes())?;
+        output.write_all(&4u16.to_le_bytes())?;
+        output.write_all(&0i32.to_le_bytes())?;
+        output.write_all(&0u32.to_le_bytes())?;
+        output.write_all(&SNAPLEN.to_le_bytes())?;
+        output.write_all(&LINKTYPE_WIRESHARK_UPPER_PDU.to_le_bytes())?;
+        Ok(Self { output, day_start })
+    }
+}
+
+impl<W: Write> EventWriter for PcapWriter<W> {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let message = match l3_message(event) {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        let packet = exported_pdu(message.protocol.dissector(), &message.contents);
+        let seconds = self.day_start + event.timestamp / 1000;
+        let microseconds = (event.timestamp % 1000) * 1000;
+
+        self.output.write_all(&(seconds as u32).to_le_bytes())?;
+        self.output.write_all(&(microseconds as u32).to_le_bytes())?;
+        self.output.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.output.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.output.write_all(&packet)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Builds an exported PDU packet: a protocol name tag, padded to four bytes,
+/// followed by the end of options tag and the message itself.
+pub fn exported_pdu(dissector: &str, contents: &[u8]) -> Vec<u8> {
+    let padded_length = (dissector.len() + 3) & !3;
+    let mut packet = Vec::with_capacity(8 + padded_length + contents.len());
+
+    packet.extend_from_slice(&EXP_PDU_TAG_PROTO_NAME.to_be_bytes());
+    packet.extend_from_slice(&(padded_length as u16).to_be_bytes());
+    packet.extend_from_slice(dissector.as_bytes());
+    packet.resize(4 + padded_length, 0);
+    packet.extend_from_slice(&EXP_PDU_TAG_END_OF_OPT.to_be_bytes());
+    packet.extend_from_slice(&0u16.to_be_bytes());
+    packet.extend_from_slice(contents);
+    packet
+}
+
+/// Trace date from a file named like `A20221015.1000+0200-...`, as seconds
+/// since the epoch at local midnight, or 0 if the name carries no date. The
+/// UTC offset following the start time is taken as the local time zone.
+pub fn file_day_start(filename: &str) -> u64 {
+    let name = Path::new(filename)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let date = match name.strip_prefix('A') {
+        Some(rest) if rest.len() >= 8 && rest[..8].bytes().all(|b| b.is_ascii_digit()) => {
+            &rest[..8]
+        }
+        _ => return 0,
+    };
+    let year: i64 = date[..4].parse().unwrap();
+    let month: i64 = date[4..6].parse().unwrap();
+    let day: i64 = date[6..8].parse().unwrap();
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return 0;
+    }
+    let midnight = days_from_civil(year, month, day) * SECONDS_PER_DAY as i64;
+    (midnight - utc_offset(&name[9..]).unwrap_or(0)).max(0) as u64
+}
+
+/// Offset in seconds of the `+hhmm` or `-hhmm` following the `.hhmm` start
+/// time of the file name.
+fn utc_offset(name: &str) -> Option<i64> {
+    let offset = name.get(5..10)?;
+    let sign = match offset.as_bytes()[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    if !offset[1..].bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = offset[1..3].parse().ok()?;
+    let minutes: i64 = offset[3..5].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_exported_pdu() {
+        assert_eq!(
+            exported_pdu("s1ap", &[0x00, 0x17]),
+            vec![0, 12, 0, 4, b's', b'1', b'a', b'p', 0, 0, 0, 0, 0x00, 0x17]
+        );
+        assert_eq!(exported_pdu("lte_rrc.ul_ccch", &[]).len(), 4 + 16 + 4);
+    }
+
+    #[test]
+    fn test_file_day_start() {
+        assert_eq!(
+            file_day_start("/data/A20221015.1000+0200-1015+0200_1_celltracefile_DUL1_1.bin.gz"),
+            1665792000 - 7200
+        );
+        assert_eq!(
+            file_day_start("A20221015.1000-0330-1015-0330_celltracefile.bin.gz"),
+            1665792000 + 12600
+        );
+        assert_eq!(file_day_start("A20221015.bin.gz"), 1665792000);
+        assert_eq!(file_day_start("trace.bin.gz"), 0);
+    }
+
+    #[test]
+    fn test_pcap_writer() {
+        let mut output: Vec<u8> = Vec::new();
+        {
+            let mut writer = PcapWriter::new(&mut output, 0).unwrap();
+            writer
+                .write(&event(
+                    "S1_UE_CONTEXT_RELEASE_COMPLETE",
+                    1500,
+                    &[
+                        ("EVENT_PARAM_MESSAGE_DIRECTION", "EVENT_VALUE_SENT"),
+                        ("EVENT_PARAM_L3MESSAGE_CONTENTS", "2017"),
+                    ],
+                ))
+                .unwrap();
+            writer
+                .write(&event("INTERNAL_PROC_UE_CTXT_RELEASE", 1600, &[]))
+                .unwrap();
+            writer.finish().unwrap();
+        }
+
+        assert_eq!(output.len(), 24 + 16 + 14);
+        assert_eq!(&output[20..24], &252u32.to_le_bytes());
+        assert_eq!(&output[24..28], &1u32.to_le_bytes());
+        assert_eq!(&output[28..32], &500000u32.to_le_bytes());
+        assert_eq!(&output[52..54], &[0x20, 0x17]);
+    }
+}
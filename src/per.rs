@@ -0,0 +1,229 @@
+/// Reader for ASN.1 packed encoding rules, in the unaligned (UPER, used by
+/// RRC) or aligned (APER, used by S1AP and X2AP) variant. Every read returns
+/// `None` once the data runs out.
+pub struct PerReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    aligned: bool,
+}
+
+impl<'a> PerReader<'a> {
+    pub fn unaligned(data: &'a [u8]) -> PerReader<'a> {
+        PerReader {
+            data,
+            position: 0,
+            aligned: false,
+        }
+    }
+
+    pub fn aligned(data: &'a [u8]) -> PerReader<'a> {
+        PerReader {
+            data,
+            position: 0,
+            aligned: true,
+        }
+    }
+
+    pub fn bit(&mut self) -> Option<bool> {
+        let byte = self.data.get(self.position / 8)?;
+        let bit = byte & (0x80 >> (self.position % 8)) != 0;
+        self.position += 1;
+        Some(bit)
+    }
+
+    pub fn bits(&mut self, count: usize) -> Option<u64> {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit()? as u64;
+        }
+        Some(value)
+    }
+
+    /// Skips to the next octet boundary; a no-op for UPER.
+    pub fn align(&mut self) {
+        if self.aligned {
+            self.position = self.position.div_ceil(8) * 8;
+        }
+    }
+
+    /// Constrained whole number in `min..=max`.
+    pub fn constrained(&mut self, min: i64, max: i64) -> Option<i64> {
+        let range = (max - min + 1) as u64;
+        if !self.aligned || range <= 255 {
+            return Some(min + self.bits(bits_for(range))? as i64);
+        }
+        let value = if range == 256 {
+            self.align();
+            self.bits(8)?
+        } else if range <= 65536 {
+            self.align();
+            self.bits(16)?
+        } else {
+            let octets = self.bits(bits_for(octets_for(range) as u64))? as usize + 1;
+            self.align();
+            self.bits(8 * octets)?
+        };
+        Some(min + value as i64)
+    }
+
+    /// Constrained whole number in `min..=max` with an extension marker.
+    /// Values outside the root range are encoded as unconstrained.
+    pub fn extensible_constrained(&mut self, min: i64, max: i64) -> Option<i64> {
+        if !self.bit()? {
+            return self.constrained(min, max);
+        }
+        let octets = self.length()?;
+        if octets == 0 || octets > 8 {
+            return None;
+        }
+        let value = self.bits(8 * octets)?;
+        let shift = 64 - 8 * octets;
+        Some(((value << shift) as i64) >> shift)
+    }
+
+    /// Index of a CHOICE alternative. Alternatives added as extensions are
+    /// numbered after the `count` root ones.
+    pub fn choice(&mut self, count: usize, extensible: bool) -> Option<usize> {
+        if extensible && self.bit()? {
+            return Some(count + self.normally_small()?);
+        }
+        Some(self.constrained(0, count as i64 - 1)? as usize)
+    }
+
+    pub fn enumerated(&mut self, count: usize, extensible: bool) -> Option<usize> {
+        self.choice(count, extensible)
+    }
+
+    /// SEQUENCE preamble: whether extension additions follow the root
+    /// components, and the presence bitmap of the optional ones.
+    pub fn sequence(&mut self, extensible: bool, optionals: usize) -> Option<(bool, Vec<bool>)> {
+        let extended = extensible && self.bit()?;
+        let present = (0..optionals)
+            .map(|_| self.bit())
+            .collect::<Option<Vec<bool>>>()?;
+        Some((extended, present))
+    }
+
+    /// Number of elements of a SEQUENCE OF or string with a size constraint.
+    pub fn size(&mut self, min: usize, max: usize) -> Option<usize> {
+        if min == max {
+            return Some(min);
+        }
+        if max < 65536 {
+            return Some(self.constrained(min as i64, max as i64)? as usize);
+        }
+        self.length()
+    }
+
+    /// Unconstrained length determinant. Fragmented lengths are not supported.
+    pub fn length(&mut self) -> Option<usize> {
+        self.align();
+        if !self.bit()? {
+            return Some(self.bits(7)? as usize);
+        }
+        if !self.bit()? {
+            return Some(self.bits(14)? as usize);
+        }
+        None
+    }
+
+    /// Normally small whole number: six bits below 64, otherwise a length
+    /// in octets followed by the value.
+    pub fn normally_small(&mut self) -> Option<usize> {
+        if !self.bit()? {
+            return Some(self.bits(6)? as usize);
+        }
+        let length = self.length()?;
+        if length == 0 || length > 8 {
+            return None;
+        }
+        Some(self.bits(8 * length)? as usize)
+    }
+
+    /// Fixed-size BIT STRING of at most 64 bits.
+    pub fn bit_string(&mut self, size: usize) -> Option<u64> {
+        if size > 16 {
+            self.align();
+        }
+        self.bits(size)
+    }
+
+    /// OCTET STRING of `count` octets, with its length already known.
+    pub fn octets(&mut self, count: usize) -> Option<Vec<u8>> {
+        if count > 2 {
+            self.align();
+        }
+        (0..count).map(|_| self.bits(8).map(|b| b as u8)).collect()
+    }
+
+    /// OCTET STRING without a size constraint.
+    pub fn octet_string(&mut self) -> Option<Vec<u8>> {
+        let count = self.length()?;
+        self.align();
+        (0..count).map(|_| self.bits(8).map(|b| b as u8)).collect()
+    }
+
+    /// Open type: the encoding of a nested value, prefixed with its length.
+    pub fn open_type(&mut self) -> Option<Vec<u8>> {
+        self.octet_string()
+    }
+
+    /// Skips the extension additions of a SEQUENCE whose extension bit was set.
+    pub fn skip_extensions(&mut self) -> Option<()> {
+        let count = self.normally_small()? + 1;
+        let present = self.bits(count)?;
+        for _ in 0..present.count_ones() {
+            self.open_type()?;
+        }
+        Some(())
+    }
+}
+
+/// Bits needed for a constrained whole number with `range` possible values.
+fn bits_for(range: u64) -> usize {
+    (64 - (range.max(1) - 1).leading_zeros()) as usize
+}
+
+fn octets_for(range: u64) -> usize {
+    bits_for(range).div_ceil(8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::unusual_byte_groupings)]
+    fn test_unaligned() {
+        let mut reader = PerReader::unaligned(&[0b1011_0010, 0b1000_0001, 0xab]);
+        assert_eq!(reader.choice(2, false), Some(1));
+        assert_eq!(reader.constrained(0, 503), Some(0b0110_0101_0));
+        assert_eq!(reader.sequence(true, 2), Some((false, vec![false, false])));
+        assert_eq!(reader.bits(4), Some(3));
+        assert_eq!(reader.bits(9), None);
+    }
+
+    #[test]
+    fn test_aligned() {
+        let mut reader = PerReader::aligned(&[0x80, 0x00, 0x0c, 0x03, 0x01, 0x02, 0x03, 0x40]);
+        assert_eq!(reader.bit(), Some(true));
+        assert_eq!(reader.constrained(0, 65535), Some(12));
+        assert_eq!(reader.octet_string(), Some(vec![1, 2, 3]));
+        assert_eq!(reader.enumerated(3, false), Some(1));
+    }
+
+    #[test]
+    fn test_extensible_constrained() {
+        let mut reader = PerReader::aligned(&[0x2c, 0x01, 0x10]);
+        assert_eq!(reader.extensible_constrained(0, 15), Some(5));
+        assert_eq!(reader.extensible_constrained(0, 15), Some(16));
+    }
+
+    #[test]
+    fn test_normally_small() {
+        let mut reader = PerReader::unaligned(&[0xc0, 0x50, 0x00]);
+        assert_eq!(reader.choice(2, true), Some(2 + 64));
+        let mut reader = PerReader::unaligned(&[0x06]);
+        assert_eq!(reader.normally_small(), Some(3));
+    }
+}
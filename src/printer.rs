@@ -1,5 +1,19 @@
+use super::compare::Comparison;
+use super::config::SchemaQuery;
+use super::distance::DistanceHistogram;
+use super::handover::HandoverAnalysis;
+use super::kpi::{CellKpis, DroppedUe, KpiCounter};
+use super::l3::{cause, decode_event, L3Field, L3_MESSAGE_CONTENTS};
+use super::neighbour::NeighbourRelation;
+use super::schema::{event_layout, find_event, list_events, search_parameters};
+use super::schema_diff::SchemaDiff;
+use super::session::{build_sessions, format_time, sessions_with_ueref, Session};
+use super::timeseries::TimeSeries;
 use super::trace_reader::{TraceEvent, TraceParameter};
+use super::units::{convert, converted_column, format_converted, CellMeasurement};
+use super::xml_parser::Event;
 use indexmap::IndexMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tabled::{builder::Builder, Style};
 
 pub fn print_summary(summary: IndexMap<String, String>) {
@@ -15,13 +29,16 @@ pub fn print_trace_in_row(events: &[TraceEvent]) {
     let mut max_columns = 0;
 
     for event in events {
-        max_columns = std::cmp::max(max_columns, event.parameters.len() + 1);
-        let mut row: Vec<_> = event
-            .parameters
-            .iter()
-            .map(|obj| format!("{}:{}", obj.name, obj.value))
-            .collect();
-        row.insert(0, event.name.to_string());
+        let mut row = vec![event.name.to_string()];
+        for parameter in &event.parameters {
+            row.push(format!("{}:{}", parameter.name, parameter.value));
+            if let Some(column) = converted_column(&parameter.name) {
+                if let Some((value, _)) = convert(&parameter.name, &parameter.value) {
+                    row.push(format!("{}:{}", column, format_converted(value, "")));
+                }
+            }
+        }
+        max_columns = std::cmp::max(max_columns, row.len());
         builder.add_record(row);
     }
 
@@ -31,16 +48,28 @@ pub fn print_trace_in_row(events: &[TraceEvent]) {
     println!("{}", table);
 }
 
-pub fn print_trace_by_ueref(events: &mut Vec<TraceEvent>, ueref: &str) {
-    let target_ueref = TraceParameter {
-        name: "EVENT_PARAM_RAC_UE_REF".to_string(),
-        value: ueref.to_string(),
-    };
-
+/// Prints the events of the UE sessions that include `ueref`, so S1 and X2
+/// events linked through the S1AP ids are shown along with the RRC ones.
+pub fn print_trace_by_ueref(events: &mut [TraceEvent], ueref: &str) {
     events.sort_by_key(|x| x.timestamp);
 
-    for event in events {
-        if ueref == "all".to_string() || event.parameters.contains(&target_ueref) {
+    let selected: Option<HashSet<usize>> = if ueref == "all" {
+        None
+    } else {
+        let sessions = build_sessions(events);
+        Some(
+            sessions_with_ueref(&sessions, ueref)
+                .iter()
+                .flat_map(|session| session.events.iter().copied())
+                .collect(),
+        )
+    };
+
+    for (index, event) in events.iter().enumerate() {
+        if selected
+            .as_ref()
+            .is_none_or(|selected| selected.contains(&index))
+        {
             let dl_direction = TraceParameter {
                 name: "EVENT_PARAM_MESSAGE_DIRECTION".to_string(),
                 value: "EVENT_VALUE_SENT".to_string(),
@@ -61,30 +90,595 @@ pub fn print_trace_by_ueref(events: &mut Vec<TraceEvent>, ueref: &str) {
                 reverse_s1_x2_direction = "<---";
             }
 
+            let message = decode_event(event);
+            let cause = match message.as_ref().and_then(cause) {
+                Some(cause) => format!(" ({})", cause),
+                None => "".to_string(),
+            };
+
             if event.name.starts_with("S1") || event.name.starts_with("X2") {
-                println!("-   {} {}", event.name, reverse_s1_x2_direction);
+                println!("-   {} {}{}", event.name, reverse_s1_x2_direction, cause);
             } else {
-                println!("{}{}", direction, event.name);
+                println!("{}{}{}", direction, event.name, cause);
             }
 
             for parameter in &event.parameters {
-                if parameter.name.contains("EVENT_ARRAY_TA") {
-                    let value: f32 = parameter.value.parse::<f32>().unwrap()
-                        * f32::powi(10.0, -9)
-                        * 3.0
-                        * f32::powi(10.0, 8)
-                        * 32.55
-                        / 1000.0;
-                    println!("            {:<40}: {:.1}", parameter.name, value);
-                } else if parameter.name.contains("EVENT_PARAM_SERVING_RSRP")
-                    || parameter.name.contains("EVENT_PARAM_NEIGHBOR_RSRP")
-                {
-                    let value: i32 = parameter.value.parse::<i32>().unwrap() - 140;
-                    println!("            {:<40}: {}", parameter.name, value);
-                } else {
-                    println!("            {:<40}: {}", parameter.name, parameter.value);
+                match convert(&parameter.name, &parameter.value) {
+                    Some((value, unit)) => println!(
+                        "            {:<40}: {}",
+                        parameter.name,
+                        format_converted(value, unit)
+                    ),
+                    None => println!("            {:<40}: {}", parameter.name, parameter.value),
+                }
+                if parameter.name == L3_MESSAGE_CONTENTS {
+                    if let Some(message) = &message {
+                        print_l3_field(message, 0);
+                    }
                 }
             }
         }
     }
 }
+
+pub fn print_sessions(sessions: &[Session]) {
+    let mut builder = Builder::default();
+    for session in sessions {
+        let handover = match (session.handover_in, session.handover_out) {
+            (true, true) => "in/out",
+            (true, false) => "in",
+            (false, true) => "out",
+            (false, false) => "",
+        };
+        builder.add_record(vec![
+            session.id.to_string(),
+            format_time(session.start),
+            format_time(session.end),
+            session.duration().to_string(),
+            session.rac_ue_refs.join(" "),
+            session.enb_s1ap_ids.join(" "),
+            session.mme_s1ap_ids.join(" "),
+            session.cells.join(" "),
+            handover.to_string(),
+            session
+                .end_cause
+                .as_ref()
+                .map(|cause| cause.to_string())
+                .unwrap_or_default(),
+            session.events.len().to_string(),
+        ]);
+    }
+    builder.set_columns([
+        "session",
+        "start",
+        "end",
+        "ms",
+        "rac ue ref",
+        "enb s1ap id",
+        "mme s1ap id",
+        "cells",
+        "handover",
+        "end cause",
+        "events",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_kpis(kpis: &[CellKpis]) {
+    let mut builder = Builder::default();
+    for cell in kpis {
+        for (kpi, counter) in &cell.kpis {
+            let rate = |rate: Option<f64>| match rate {
+                Some(rate) => format!("{:.2}", rate),
+                None => "".to_string(),
+            };
+            let causes: Vec<String> = counter
+                .failure_causes
+                .iter()
+                .map(|(cause, count)| format!("({}:{})", cause, count))
+                .collect();
+            builder.add_record(vec![
+                cell.cell.to_string(),
+                format_time(cell.bin_start),
+                kpi.to_string(),
+                counter.attempts.to_string(),
+                counter.successes.to_string(),
+                rate(counter.success_rate()),
+                rate(counter.failure_rate()),
+                causes.join(" "),
+            ]);
+        }
+    }
+    builder.set_columns([
+        "cell",
+        "bin",
+        "kpi",
+        "attempts",
+        "successes",
+        "success %",
+        "failure %",
+        "failure causes",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_dropped(dropped: &[DroppedUe]) {
+    let mut builder = Builder::default();
+    for ue in dropped {
+        let measurements: Vec<String> = ue
+            .measurements
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        builder.add_record(vec![
+            ue.session
+                .map(|session| session.to_string())
+                .unwrap_or_default(),
+            ue.rac_ue_refs.join(" "),
+            ue.cell.to_string(),
+            format_time(ue.timestamp),
+            ue.release.to_string(),
+            ue.cause.to_string(),
+            measurements.join(" "),
+        ]);
+    }
+    builder.set_columns([
+        "session",
+        "rac ue ref",
+        "cell",
+        "time",
+        "release",
+        "cause",
+        "last measurements",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_unclassified_causes(causes: &BTreeMap<String, u64>) {
+    if causes.is_empty() {
+        return;
+    }
+    let mut builder = Builder::default();
+    for (cause, count) in causes {
+        builder.add_record(vec![cause.to_string(), count.to_string()]);
+    }
+    builder.set_columns(["unclassified release cause", "releases"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_handovers(analysis: &HandoverAnalysis) {
+    let causes = |counter: &KpiCounter| {
+        let causes: Vec<String> = counter
+            .failure_causes
+            .iter()
+            .map(|(cause, count)| format!("({}:{})", cause, count))
+            .collect();
+        causes.join(" ")
+    };
+    let mut builder = Builder::default();
+    for pair in &analysis.pairs {
+        let counters = pair.counters();
+        builder.add_record(vec![
+            pair.source.to_string(),
+            pair.target.to_string(),
+            pair.kind.to_string(),
+            counters.preparation.attempts.to_string(),
+            counters.execution.attempts.to_string(),
+            counters.execution.successes.to_string(),
+            match counters.success_rate() {
+                Some(rate) => format!("{:.2}", rate),
+                None => "".to_string(),
+            },
+            causes(&counters.preparation),
+            causes(&counters.execution),
+        ]);
+    }
+    builder.set_columns([
+        "source",
+        "target",
+        "kind",
+        "prepared",
+        "executed",
+        "succeeded",
+        "success %",
+        "preparation failures",
+        "execution failures",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+
+    let mut builder = Builder::default();
+    for ping_pong in &analysis.ping_pongs {
+        builder.add_record(vec![
+            ping_pong.ue.to_string(),
+            ping_pong.source.to_string(),
+            ping_pong.target.to_string(),
+            format_time(ping_pong.left),
+            format_time(ping_pong.returned),
+        ]);
+    }
+    builder.set_columns(["ue", "source", "target", "left", "returned"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_measurements(measurements: &[CellMeasurement]) {
+    let mut builder = Builder::default();
+    for measurement in measurements {
+        let statistics = &measurement.statistics;
+        let value = |value: f64| format!("{:.1}", value);
+        builder.add_record(vec![
+            measurement.cell.to_string(),
+            measurement.parameter.to_string(),
+            measurement.unit.to_string(),
+            statistics.count.to_string(),
+            value(statistics.min),
+            value(statistics.p10),
+            value(statistics.p50),
+            value(statistics.p90),
+            value(statistics.max),
+            value(statistics.mean),
+        ]);
+    }
+    builder.set_columns([
+        "cell",
+        "parameter",
+        "unit",
+        "count",
+        "min",
+        "p10",
+        "p50",
+        "p90",
+        "max",
+        "mean",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_distances(histograms: &[DistanceHistogram], radius_m: f64) {
+    let mut builder = Builder::default();
+    for histogram in histograms {
+        let statistics = &histogram.statistics;
+        let bins: Vec<String> = histogram
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(bin, count)| format!("({}:{})", bin as f64 * histogram.bin_metres, count))
+            .collect();
+        builder.add_record(vec![
+            histogram.cell.to_string(),
+            statistics.count.to_string(),
+            format!("{:.0}", statistics.p50),
+            format!("{:.0}", statistics.p90),
+            format!("{:.0}", statistics.max),
+            format!("{:.2}", histogram.beyond_radius_rate()),
+            if histogram.overshooting { "yes" } else { "" }.to_string(),
+            bins.join(" "),
+        ]);
+    }
+    builder.set_columns([
+        "cell".to_string(),
+        "samples".to_string(),
+        "p50 m".to_string(),
+        "p90 m".to_string(),
+        "max m".to_string(),
+        format!("beyond {} m %", radius_m),
+        "overshooting".to_string(),
+        "histogram (from m:count)".to_string(),
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_neighbours(relations: &[NeighbourRelation]) {
+    let mut builder = Builder::default();
+    for relation in relations {
+        let note = if relation.handover_attempts == 0 {
+            "no handover"
+        } else if relation.pci_confusion() {
+            "PCI confusion"
+        } else {
+            ""
+        };
+        builder.add_record(vec![
+            relation.serving_cell.to_string(),
+            relation.earfcn.clone().unwrap_or_default(),
+            relation.pci.to_string(),
+            relation.reports.to_string(),
+            format!("{:.1}", relation.mean_rsrp_delta),
+            relation.handover_attempts.to_string(),
+            relation.target_cells.join(" "),
+            note.to_string(),
+        ]);
+    }
+    builder.set_columns([
+        "serving cell",
+        "earfcn",
+        "pci",
+        "reports",
+        "rsrp delta dB",
+        "handover attempts",
+        "target cells",
+        "note",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+pub fn print_series(series: &TimeSeries) {
+    let mut builder = Builder::default();
+    for count in &series.event_counts {
+        builder.add_record(vec![
+            format_time(count.bin_start),
+            count.cell.to_string(),
+            count.event.to_string(),
+            count.count.to_string(),
+        ]);
+    }
+    builder.set_columns(["bin", "cell", "event", "count"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+
+    let mut builder = Builder::default();
+    for parameter in &series.parameters {
+        let statistics = &parameter.statistics;
+        let value = |value: f64| format!("{:.1}", value);
+        builder.add_record(vec![
+            format_time(parameter.bin_start),
+            parameter.cell.to_string(),
+            parameter.event.to_string(),
+            parameter.parameter.to_string(),
+            statistics.count.to_string(),
+            value(statistics.min),
+            value(statistics.mean),
+            value(statistics.max),
+            value(statistics.p10),
+            value(statistics.p50),
+            value(statistics.p90),
+        ]);
+    }
+    builder.set_columns([
+        "bin",
+        "cell",
+        "event",
+        "parameter",
+        "count",
+        "min",
+        "mean",
+        "max",
+        "p10",
+        "p50",
+        "p90",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+/// Prints the comparison, with only the `top` largest parameter shifts.
+pub fn print_comparison(comparison: &Comparison, top: usize) {
+    println!("before: {}", comparison.before_files.join(" "));
+    println!("after:  {}", comparison.after_files.join(" "));
+
+    let mut builder = Builder::default();
+    for event in &comparison.events {
+        builder.add_record(vec![
+            event.event.to_string(),
+            event.before.to_string(),
+            event.after.to_string(),
+            format!("{:+}", event.delta),
+        ]);
+    }
+    builder.set_columns(["event", "before", "after", "delta"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+
+    let mean = |mean: Option<f64>| mean.map_or("".to_string(), |mean| format!("{:.2}", mean));
+    let mut builder = Builder::default();
+    for shift in comparison.parameters.iter().take(top) {
+        builder.add_record(vec![
+            shift.event.to_string(),
+            shift.parameter.to_string(),
+            shift.measure.to_string(),
+            format!("{:.3}", shift.distance),
+            mean(shift.mean_before),
+            mean(shift.mean_after),
+        ]);
+    }
+    builder.set_columns([
+        "event",
+        "parameter",
+        "measure",
+        "distance",
+        "mean before",
+        "mean after",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+
+    let rate = |rate: Option<f64>| rate.map_or("".to_string(), |rate| format!("{:.2}", rate));
+    let mut builder = Builder::default();
+    for kpi in &comparison.kpis {
+        let (before, after) = (kpi.before.success_rate(), kpi.after.success_rate());
+        builder.add_record(vec![
+            kpi.kpi.to_string(),
+            kpi.before.attempts.to_string(),
+            kpi.after.attempts.to_string(),
+            rate(before),
+            rate(after),
+            match (before, after) {
+                (Some(before), Some(after)) => format!("{:+.2}", after - before),
+                _ => "".to_string(),
+            },
+        ]);
+    }
+    builder.set_columns([
+        "kpi",
+        "attempts before",
+        "attempts after",
+        "success % before",
+        "success % after",
+        "delta",
+    ]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+
+    let mut builder = Builder::default();
+    for cause in &comparison.causes {
+        builder.add_record(vec![
+            cause.event.to_string(),
+            cause.parameter.to_string(),
+            cause.value.to_string(),
+            cause.change.to_string(),
+            cause.count.to_string(),
+        ]);
+    }
+    builder.set_columns(["event", "parameter", "cause", "change", "count"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+fn print_l3_field(field: &L3Field, depth: usize) {
+    let indent = "                ".to_string() + &"  ".repeat(depth);
+    match &field.value {
+        Some(value) => println!("{}{}: {}", indent, field.name, value),
+        None => println!("{}{}", indent, field.name),
+    }
+    for child in &field.children {
+        print_l3_field(child, depth + 1);
+    }
+}
+
+pub fn print_schema(events: &HashMap<u16, Event>, query: &SchemaQuery) {
+    match query {
+        SchemaQuery::Events => print_schema_events(events),
+        SchemaQuery::Event(key) => match find_event(events, key) {
+            Some(event) => print_schema_event(event),
+            None => eprintln!("event {} is not defined in the schema", key),
+        },
+        SchemaQuery::Search(pattern) => print_schema_search(events, pattern),
+        SchemaQuery::Validate => println!("schema is valid, {} events defined", events.len()),
+    }
+}
+
+fn print_schema_events(events: &HashMap<u16, Event>) {
+    let mut builder = Builder::default();
+    for summary in list_events(events) {
+        builder.add_record(vec![
+            summary.id.to_string(),
+            summary.name,
+            summary.parameter_count.to_string(),
+        ]);
+    }
+    builder.set_columns(["id", "name", "parameters"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+fn print_schema_event(event: &Event) {
+    println!("{} (id {})", event.name, event.id);
+
+    let mut builder = Builder::default();
+    for parameter in event_layout(event) {
+        let enumeration: Vec<String> = parameter
+            .enumeration
+            .iter()
+            .map(|(id, value)| format!("{}={}", id, value))
+            .collect();
+        builder.add_record(vec![
+            optional_to_string(parameter.offset),
+            optional_to_string(parameter.width),
+            parameter.name,
+            parameter.param_type,
+            parameter.length_reference.unwrap_or_default(),
+            enumeration.join(" "),
+        ]);
+    }
+    builder.set_columns(["offset", "width", "name", "type", "length", "values"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+fn print_schema_search(events: &HashMap<u16, Event>, pattern: &str) {
+    let mut builder = Builder::default();
+    for found in search_parameters(events, pattern) {
+        builder.add_record(vec![
+            found.parameter,
+            found.event_id.to_string(),
+            found.event_name,
+        ]);
+    }
+    builder.set_columns(["parameter", "event id", "event"]);
+    let table = builder.build().with(Style::ascii_rounded());
+    println!("{}", table);
+}
+
+fn optional_to_string(value: Option<i64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "var".to_string(),
+    }
+}
+
+pub fn print_schema_diff(diff: &SchemaDiff, json: bool) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(diff).unwrap());
+        return;
+    }
+    if diff.is_empty() {
+        println!("schemas are identical");
+        return;
+    }
+
+    print_names("added events", &diff.added_events);
+    print_names("removed events", &diff.removed_events);
+    if !diff.changed_ids.is_empty() {
+        println!("changed event ids");
+        for change in &diff.changed_ids {
+            println!(
+                "    {:<40}: {} -> {}",
+                change.event, change.old_id, change.new_id
+            );
+        }
+    }
+    for event in &diff.events {
+        println!("{}", event.event);
+        print_names("    added parameters", &event.added_parameters);
+        print_names("    removed parameters", &event.removed_parameters);
+        if event.reordered {
+            println!("    parameters reordered");
+        }
+    }
+    for parameter in &diff.parameters {
+        println!("{}", parameter.parameter);
+        if parameter.old_width != parameter.new_width {
+            println!(
+                "    width: {} -> {}",
+                parameter.old_width, parameter.new_width
+            );
+        }
+        for (id, value) in &parameter.added_values {
+            println!("    value added: {}={}", id, value);
+        }
+        for (id, value) in &parameter.removed_values {
+            println!("    value removed: {}={}", id, value);
+        }
+        for (id, old, new) in &parameter.renamed_values {
+            println!("    value renamed: {}={} -> {}", id, old, new);
+        }
+    }
+}
+
+fn print_names(title: &str, names: &[String]) {
+    if !names.is_empty() {
+        println!("{}", title);
+        for name in names {
+            println!("    {}", name);
+        }
+    }
+}
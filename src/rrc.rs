@@ -0,0 +1,827 @@
+use super::l3::{L3Field, RrcChannel};
+use super::nas;
+use super::per::PerReader;
+
+const UL_CCCH_MESSAGES: [&str; 2] = [
+    "rrcConnectionReestablishmentRequest",
+    "rrcConnectionRequest",
+];
+const DL_CCCH_MESSAGES: [&str; 4] = [
+    "rrcConnectionReestablishment",
+    "rrcConnectionReestablishmentReject",
+    "rrcConnectionReject",
+    "rrcConnectionSetup",
+];
+const UL_DCCH_MESSAGES: [&str; 16] = [
+    "csfbParametersRequestCDMA2000",
+    "measurementReport",
+    "rrcConnectionReconfigurationComplete",
+    "rrcConnectionReestablishmentComplete",
+    "rrcConnectionSetupComplete",
+    "securityModeComplete",
+    "securityModeFailure",
+    "ueCapabilityInformation",
+    "ulHandoverPreparationTransfer",
+    "ulInformationTransfer",
+    "counterCheckResponse",
+    "ueInformationResponse-r9",
+    "proximityIndication-r9",
+    "rnReconfigurationComplete-r10",
+    "mbmsCountingResponse-r10",
+    "interFreqRSTDMeasurementIndication-r10",
+];
+const DL_DCCH_MESSAGES: [&str; 16] = [
+    "csfbParametersResponseCDMA2000",
+    "dlInformationTransfer",
+    "handoverFromEUTRAPreparationRequest",
+    "mobilityFromEUTRACommand",
+    "rrcConnectionReconfiguration",
+    "rrcConnectionRelease",
+    "securityModeCommand",
+    "ueCapabilityEnquiry",
+    "counterCheck",
+    "ueInformationRequest-r9",
+    "loggedMeasurementConfiguration-r10",
+    "rnReconfiguration-r10",
+    "rrcConnectionResume-r13",
+    "spare3",
+    "spare2",
+    "spare1",
+];
+const BCCH_DL_SCH_MESSAGES: [&str; 2] = ["systemInformation", "systemInformationBlockType1"];
+const PCCH_MESSAGES: [&str; 1] = ["paging"];
+
+const ESTABLISHMENT_CAUSES: [&str; 8] = [
+    "emergency",
+    "highPriorityAccess",
+    "mt-Access",
+    "mo-Signalling",
+    "mo-Data",
+    "delayTolerantAccess-v1020",
+    "mo-VoiceCall-v1280",
+    "spare1",
+];
+const REESTABLISHMENT_CAUSES: [&str; 4] = [
+    "reconfigurationFailure",
+    "handoverFailure",
+    "otherFailure",
+    "spare1",
+];
+const RELEASE_CAUSES: [&str; 4] = [
+    "loadBalancingTAUrequired",
+    "other",
+    "cs-FallbackHighPriority-v1020",
+    "rrc-Suspend-v1320",
+];
+const DEDICATED_INFO_TYPES: [&str; 3] = [
+    "dedicatedInfoNAS",
+    "dedicatedInfoCDMA2000-1XRTT",
+    "dedicatedInfoCDMA2000-HRPD",
+];
+const NEIGHBOUR_RESULTS: [&str; 4] = [
+    "measResultListEUTRA",
+    "measResultListUTRA",
+    "measResultListGERAN",
+    "measResultsCDMA2000",
+];
+const RECONFIGURATION_IES: [&str; 5] = [
+    "measConfig",
+    "mobilityControlInfo",
+    "dedicatedInfoNASList",
+    "radioResourceConfigDedicated",
+    "securityConfigHO",
+];
+const MEAS_OBJECTS: [&str; 4] = [
+    "measObjectEUTRA",
+    "measObjectUTRA",
+    "measObjectGERAN",
+    "measObjectCDMA2000",
+];
+const REPORT_CONFIGS: [&str; 2] = ["reportConfigEUTRA", "reportConfigInterRAT"];
+const EUTRA_EVENTS: [&str; 5] = ["eventA1", "eventA2", "eventA3", "eventA4", "eventA5"];
+const CIPHERING_ALGORITHMS: [&str; 8] = [
+    "eea0",
+    "eea1",
+    "eea2",
+    "eea3-v1130",
+    "spare4",
+    "spare3",
+    "spare2",
+    "spare1",
+];
+const INTEGRITY_ALGORITHMS: [&str; 8] = [
+    "eia0-v920",
+    "eia1",
+    "eia2",
+    "eia3-v1130",
+    "spare4",
+    "spare3",
+    "spare2",
+    "spare1",
+];
+const RAT_TYPES: [&str; 8] = [
+    "eutra",
+    "utra",
+    "geran-cs",
+    "geran-ps",
+    "cdma2000-1XRTT",
+    "nr",
+    "eutra-nr",
+    "spare1",
+];
+const DL_BANDWIDTHS: [&str; 6] = ["n6", "n15", "n25", "n50", "n75", "n100"];
+const PHICH_DURATIONS: [&str; 2] = ["normal", "extended"];
+const PHICH_RESOURCES: [&str; 4] = ["oneSixth", "half", "one", "two"];
+
+/// Decodes an LTE RRC message (3GPP TS 36.331) sent on `channel`. Only the
+/// common messages are decoded in depth; for the others the tree stops at
+/// the message name. A message cut short keeps what was decoded and ends
+/// with an `error` field.
+pub fn decode(channel: RrcChannel, contents: &[u8]) -> L3Field {
+    let mut root = L3Field::new(message_name(channel));
+    let mut reader = PerReader::unaligned(contents);
+    let decoded = match channel {
+        RrcChannel::UlCcch => decode_ul_ccch(&mut reader, &mut root),
+        RrcChannel::DlCcch => decode_dl_ccch(&mut reader, &mut root),
+        RrcChannel::UlDcch => decode_ul_dcch(&mut reader, &mut root),
+        RrcChannel::DlDcch => decode_dl_dcch(&mut reader, &mut root),
+        RrcChannel::BcchBch => decode_master_information_block(&mut reader, &mut root),
+        RrcChannel::BcchDlSch => decode_bcch_dl_sch(&mut reader, &mut root),
+        RrcChannel::Pcch => decode_pcch(&mut reader, &mut root),
+    };
+    if decoded.is_none() {
+        root.push(L3Field::leaf("error", "message truncated"));
+    }
+    root
+}
+
+fn message_name(channel: RrcChannel) -> &'static str {
+    match channel {
+        RrcChannel::UlCcch => "UL-CCCH-Message",
+        RrcChannel::DlCcch => "DL-CCCH-Message",
+        RrcChannel::UlDcch => "UL-DCCH-Message",
+        RrcChannel::DlDcch => "DL-DCCH-Message",
+        RrcChannel::BcchBch => "BCCH-BCH-Message",
+        RrcChannel::BcchDlSch => "BCCH-DL-SCH-Message",
+        RrcChannel::Pcch => "PCCH-Message",
+    }
+}
+
+/// Reads the `c1`/`messageClassExtension` choice every channel starts with
+/// and adds a node for the message found. The index is `names.len()` for
+/// the message class extension.
+fn message_type<'f>(
+    reader: &mut PerReader,
+    root: &'f mut L3Field,
+    names: &[&str],
+) -> Option<(usize, &'f mut L3Field)> {
+    if reader.choice(2, false)? != 0 {
+        return Some((names.len(), root.child("messageClassExtension")));
+    }
+    let index = reader.choice(names.len(), false)?;
+    Some((index, root.child(names[index])))
+}
+
+/// Reads `criticalExtensions` and tells whether the release 8 IEs follow.
+/// `c1` is the number of alternatives of the nested `c1` choice, if any.
+fn critical_extensions(reader: &mut PerReader, c1: Option<usize>) -> Option<bool> {
+    if reader.choice(2, false)? != 0 {
+        return Some(false);
+    }
+    match c1 {
+        Some(count) => Some(reader.choice(count, false)? == 0),
+        None => Some(true),
+    }
+}
+
+fn enumerated(reader: &mut PerReader, names: &[&str], extensible: bool) -> Option<String> {
+    let index = reader.enumerated(names.len(), extensible)?;
+    Some(match names.get(index) {
+        Some(name) => name.to_string(),
+        None => format!("extension {}", index - names.len()),
+    })
+}
+
+/// Fixed-size BIT STRING, as hex digits.
+fn bit_string(reader: &mut PerReader, size: usize) -> Option<String> {
+    let value = reader.bit_string(size)?;
+    Some(format!("{:0width$x}", value, width = size.div_ceil(4)))
+}
+
+fn digits(reader: &mut PerReader, min: usize, max: usize) -> Option<String> {
+    let count = reader.size(min, max)?;
+    (0..count)
+        .map(|_| reader.constrained(0, 9).map(|digit| digit.to_string()))
+        .collect()
+}
+
+fn transaction_id(reader: &mut PerReader, message: &mut L3Field) -> Option<()> {
+    message.push(L3Field::leaf(
+        "rrc-TransactionIdentifier",
+        reader.constrained(0, 3)?,
+    ));
+    Some(())
+}
+
+fn plmn_identity(reader: &mut PerReader, parent: &mut L3Field) -> Option<()> {
+    let plmn = parent.child("plmn-Identity");
+    let (_, present) = reader.sequence(false, 1)?;
+    if present[0] {
+        plmn.push(L3Field::leaf("mcc", digits(reader, 3, 3)?));
+    }
+    plmn.push(L3Field::leaf("mnc", digits(reader, 2, 3)?));
+    Some(())
+}
+
+fn s_tmsi(reader: &mut PerReader, parent: &mut L3Field) -> Option<()> {
+    let s_tmsi = parent.child("s-TMSI");
+    s_tmsi.push(L3Field::leaf("mmec", bit_string(reader, 8)?));
+    s_tmsi.push(L3Field::leaf("m-TMSI", bit_string(reader, 32)?));
+    Some(())
+}
+
+fn dedicated_info(reader: &mut PerReader, parent: &mut L3Field) -> Option<()> {
+    let index = reader.choice(DEDICATED_INFO_TYPES.len(), false)?;
+    let contents = reader.octet_string()?;
+    parent.push(match index {
+        0 => nas::nas_field(DEDICATED_INFO_TYPES[index], &contents),
+        _ => L3Field::leaf(DEDICATED_INFO_TYPES[index], hex::encode(contents)),
+    });
+    Some(())
+}
+
+fn decode_ul_ccch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, message) = message_type(reader, root, &UL_CCCH_MESSAGES)?;
+    match index {
+        0 => {
+            if !critical_extensions(reader, None)? {
+                return Some(());
+            }
+            let identity = message.child("ue-Identity");
+            identity.push(L3Field::leaf("c-RNTI", bit_string(reader, 16)?));
+            identity.push(L3Field::leaf("physCellId", reader.constrained(0, 503)?));
+            identity.push(L3Field::leaf("shortMAC-I", bit_string(reader, 16)?));
+            let cause = enumerated(reader, &REESTABLISHMENT_CAUSES, false)?;
+            message.push(L3Field::leaf("reestablishmentCause", cause));
+        }
+        1 => {
+            if !critical_extensions(reader, None)? {
+                return Some(());
+            }
+            let identity = message.child("ue-Identity");
+            if reader.choice(2, false)? == 0 {
+                s_tmsi(reader, identity)?;
+            } else {
+                identity.push(L3Field::leaf("randomValue", bit_string(reader, 40)?));
+            }
+            let cause = enumerated(reader, &ESTABLISHMENT_CAUSES, false)?;
+            message.push(L3Field::leaf("establishmentCause", cause));
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+fn decode_dl_ccch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, message) = message_type(reader, root, &DL_CCCH_MESSAGES)?;
+    match index {
+        0 | 3 => transaction_id(reader, message)?,
+        2 => {
+            if !critical_extensions(reader, Some(4))? {
+                return Some(());
+            }
+            reader.sequence(false, 1)?;
+            message.push(L3Field::leaf("waitTime", reader.constrained(1, 16)?));
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+fn decode_ul_dcch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, message) = message_type(reader, root, &UL_DCCH_MESSAGES)?;
+    match index {
+        1 => {
+            if !critical_extensions(reader, Some(8))? {
+                return Some(());
+            }
+            reader.sequence(false, 1)?;
+            meas_results(reader, message)?;
+        }
+        2 | 3 | 5 | 6 | 7 => transaction_id(reader, message)?,
+        4 => {
+            transaction_id(reader, message)?;
+            if !critical_extensions(reader, Some(4))? {
+                return Some(());
+            }
+            let (_, present) = reader.sequence(false, 2)?;
+            let plmn = reader.constrained(1, 6)?;
+            message.push(L3Field::leaf("selectedPLMN-Identity", plmn));
+            if present[0] {
+                let mme = message.child("registeredMME");
+                let (_, present) = reader.sequence(false, 1)?;
+                if present[0] {
+                    plmn_identity(reader, mme)?;
+                }
+                mme.push(L3Field::leaf("mmegi", bit_string(reader, 16)?));
+                mme.push(L3Field::leaf("mmec", bit_string(reader, 8)?));
+            }
+            let nas = reader.octet_string()?;
+            message.push(nas::nas_field("dedicatedInfoNAS", &nas));
+        }
+        9 => {
+            if !critical_extensions(reader, Some(4))? {
+                return Some(());
+            }
+            reader.sequence(false, 1)?;
+            dedicated_info(reader, message)?;
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+fn meas_results(reader: &mut PerReader, message: &mut L3Field) -> Option<()> {
+    let results = message.child("measResults");
+    let (_, present) = reader.sequence(true, 1)?;
+    results.push(L3Field::leaf("measId", reader.constrained(1, 32)?));
+    let pcell = results.child("measResultPCell");
+    pcell.push(L3Field::leaf("rsrpResult", reader.constrained(0, 97)?));
+    pcell.push(L3Field::leaf("rsrqResult", reader.constrained(0, 34)?));
+    if !present[0] {
+        return Some(());
+    }
+
+    let index = reader.choice(NEIGHBOUR_RESULTS.len(), true)?;
+    if index != 0 {
+        let name = NEIGHBOUR_RESULTS.get(index).unwrap_or(&"extension");
+        results.push(L3Field::new(name));
+        return Some(());
+    }
+    let list = results.child("measResultListEUTRA");
+    for _ in 0..reader.size(1, 8)? {
+        let cell = list.child("MeasResultEUTRA");
+        let (_, present) = reader.sequence(false, 1)?;
+        cell.push(L3Field::leaf("physCellId", reader.constrained(0, 503)?));
+        if present[0] {
+            cgi_info(reader, cell)?;
+        }
+        let result = cell.child("measResult");
+        let (extended, present) = reader.sequence(true, 2)?;
+        if present[0] {
+            result.push(L3Field::leaf("rsrpResult", reader.constrained(0, 97)?));
+        }
+        if present[1] {
+            result.push(L3Field::leaf("rsrqResult", reader.constrained(0, 34)?));
+        }
+        if extended {
+            reader.skip_extensions()?;
+        }
+    }
+    Some(())
+}
+
+fn cgi_info(reader: &mut PerReader, cell: &mut L3Field) -> Option<()> {
+    let info = cell.child("cgi-Info");
+    let (_, present) = reader.sequence(false, 1)?;
+    let global_id = info.child("cellGlobalId");
+    plmn_identity(reader, global_id)?;
+    global_id.push(L3Field::leaf("cellIdentity", bit_string(reader, 28)?));
+    info.push(L3Field::leaf("trackingAreaCode", bit_string(reader, 16)?));
+    if present[0] {
+        let list = info.child("plmn-IdentityList");
+        for _ in 0..reader.size(1, 5)? {
+            plmn_identity(reader, list)?;
+        }
+    }
+    Some(())
+}
+
+fn decode_dl_dcch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, message) = message_type(reader, root, &DL_DCCH_MESSAGES)?;
+    match index {
+        1 => {
+            transaction_id(reader, message)?;
+            if critical_extensions(reader, Some(4))? {
+                reader.sequence(false, 1)?;
+                dedicated_info(reader, message)?;
+            }
+        }
+        4 => {
+            transaction_id(reader, message)?;
+            if critical_extensions(reader, Some(8))? {
+                let (_, present) = reader.sequence(false, 6)?;
+                if present[0] {
+                    meas_config(reader, message)?;
+                }
+                for (name, present) in RECONFIGURATION_IES.iter().zip(present).skip(1) {
+                    if present {
+                        message.push(L3Field::new(name));
+                    }
+                }
+            }
+        }
+        5 => {
+            transaction_id(reader, message)?;
+            if critical_extensions(reader, Some(4))? {
+                let (_, present) = reader.sequence(false, 3)?;
+                let cause = enumerated(reader, &RELEASE_CAUSES, false)?;
+                message.push(L3Field::leaf("releaseCause", cause));
+                if present[0] {
+                    message.push(L3Field::new("redirectedCarrierInfo"));
+                }
+            }
+        }
+        6 => {
+            transaction_id(reader, message)?;
+            if critical_extensions(reader, Some(4))? {
+                reader.sequence(false, 1)?;
+                let config = message.child("securityConfigSMC");
+                reader.sequence(true, 0)?;
+                let algorithms = config.child("securityAlgorithmConfig");
+                let ciphering = enumerated(reader, &CIPHERING_ALGORITHMS, true)?;
+                algorithms.push(L3Field::leaf("cipheringAlgorithm", ciphering));
+                let integrity = enumerated(reader, &INTEGRITY_ALGORITHMS, true)?;
+                algorithms.push(L3Field::leaf("integrityProtAlgorithm", integrity));
+            }
+        }
+        7 => {
+            transaction_id(reader, message)?;
+            if critical_extensions(reader, Some(4))? {
+                reader.sequence(false, 1)?;
+                let request = message.child("ue-CapabilityRequest");
+                for _ in 0..reader.size(1, 8)? {
+                    let rat = enumerated(reader, &RAT_TYPES, true)?;
+                    request.push(L3Field::leaf("RAT-Type", rat));
+                }
+            }
+        }
+        0 | 2 | 3 | 8 | 9 => transaction_id(reader, message)?,
+        _ => {}
+    }
+    Some(())
+}
+
+/// Reads a `MeasConfig` up to the measurement identities, which link the
+/// `measId` of a measurement report to its measurement object. Only E-UTRA
+/// objects and report configurations are decoded; the configuration ends at
+/// the first other one, as what follows it cannot be located.
+fn meas_config(reader: &mut PerReader, message: &mut L3Field) -> Option<()> {
+    let config = message.child("measConfig");
+    let (_, present) = reader.sequence(true, 11)?;
+    if present[0] {
+        identities(reader, config, "measObjectToRemoveList", "measObjectId")?;
+    }
+    if present[1] {
+        let list = config.child("measObjectToAddModList");
+        for _ in 0..reader.size(1, 32)? {
+            let object = list.child("MeasObjectToAddMod");
+            object.push(L3Field::leaf("measObjectId", reader.constrained(1, 32)?));
+            match reader.choice(MEAS_OBJECTS.len(), true)? {
+                0 => meas_object_eutra(reader, object)?,
+                index if index < MEAS_OBJECTS.len() => {
+                    object.push(L3Field::new(MEAS_OBJECTS[index]));
+                    return Some(());
+                }
+                _ => {
+                    reader.open_type()?;
+                    object.push(L3Field::new("extension"));
+                }
+            }
+        }
+    }
+    if present[2] {
+        identities(reader, config, "reportConfigToRemoveList", "reportConfigId")?;
+    }
+    if present[3] {
+        let list = config.child("reportConfigToAddModList");
+        for _ in 0..reader.size(1, 32)? {
+            let report = list.child("ReportConfigToAddMod");
+            report.push(L3Field::leaf("reportConfigId", reader.constrained(1, 32)?));
+            let index = reader.choice(REPORT_CONFIGS.len(), false)?;
+            if index != 0 {
+                report.push(L3Field::new(REPORT_CONFIGS[index]));
+                return Some(());
+            }
+            report_config_eutra(reader, report)?;
+        }
+    }
+    if present[4] {
+        identities(reader, config, "measIdToRemoveList", "measId")?;
+    }
+    if present[5] {
+        let list = config.child("measIdToAddModList");
+        for _ in 0..reader.size(1, 32)? {
+            let identity = list.child("MeasIdToAddMod");
+            identity.push(L3Field::leaf("measId", reader.constrained(1, 32)?));
+            identity.push(L3Field::leaf("measObjectId", reader.constrained(1, 32)?));
+            identity.push(L3Field::leaf("reportConfigId", reader.constrained(1, 32)?));
+        }
+    }
+    Some(())
+}
+
+/// A list of identities in `1..=32`, such as the measurement objects to
+/// remove.
+fn identities(reader: &mut PerReader, parent: &mut L3Field, list: &str, name: &str) -> Option<()> {
+    let list = parent.child(list);
+    for _ in 0..reader.size(1, 32)? {
+        list.push(L3Field::leaf(name, reader.constrained(1, 32)?));
+    }
+    Some(())
+}
+
+fn meas_object_eutra(reader: &mut PerReader, object: &mut L3Field) -> Option<()> {
+    let eutra = object.child("measObjectEUTRA");
+    let (extended, present) = reader.sequence(true, 6)?;
+    eutra.push(L3Field::leaf("carrierFreq", reader.constrained(0, 65535)?));
+    // allowedMeasBandwidth, presenceAntennaPort1 and neighCellConfig
+    reader.enumerated(6, false)?;
+    reader.bits(3)?;
+    if present[0] {
+        reader.enumerated(31, false)?;
+    }
+    for list in [1, 3] {
+        if present[list] {
+            for _ in 0..reader.size(1, 32)? {
+                reader.constrained(1, 32)?;
+            }
+        }
+    }
+    if present[2] {
+        for _ in 0..reader.size(1, 32)? {
+            reader.constrained(1, 32)?;
+            reader.constrained(0, 503)?;
+            reader.enumerated(31, false)?;
+        }
+    }
+    if present[4] {
+        for _ in 0..reader.size(1, 32)? {
+            reader.constrained(1, 32)?;
+            let (_, present) = reader.sequence(false, 1)?;
+            reader.constrained(0, 503)?;
+            if present[0] {
+                reader.enumerated(16, false)?;
+            }
+        }
+    }
+    if present[5] {
+        reader.constrained(0, 503)?;
+    }
+    if extended {
+        reader.skip_extensions()?;
+    }
+    Some(())
+}
+
+fn report_config_eutra(reader: &mut PerReader, report: &mut L3Field) -> Option<()> {
+    let config = report.child("reportConfigEUTRA");
+    let (extended, _) = reader.sequence(true, 0)?;
+    if reader.choice(2, false)? == 0 {
+        let event = reader.choice(EUTRA_EVENTS.len(), true)?;
+        match event {
+            0 | 1 | 3 => threshold_eutra(reader)?,
+            2 => {
+                reader.constrained(-30, 30)?;
+                reader.bit()?;
+            }
+            4 => {
+                threshold_eutra(reader)?;
+                threshold_eutra(reader)?;
+            }
+            _ => {
+                reader.open_type()?;
+            }
+        }
+        let name = EUTRA_EVENTS.get(event).unwrap_or(&"extension");
+        config.push(L3Field::leaf("eventId", name));
+        // hysteresis and timeToTrigger
+        reader.constrained(0, 30)?;
+        reader.enumerated(16, false)?;
+    } else {
+        reader.enumerated(2, false)?;
+        config.push(L3Field::leaf("triggerType", "periodical"));
+    }
+    // triggerQuantity, reportQuantity, maxReportCells, reportInterval and
+    // reportAmount
+    reader.enumerated(2, false)?;
+    reader.enumerated(2, false)?;
+    reader.constrained(1, 8)?;
+    reader.enumerated(16, false)?;
+    reader.enumerated(8, false)?;
+    if extended {
+        reader.skip_extensions()?;
+    }
+    Some(())
+}
+
+fn threshold_eutra(reader: &mut PerReader) -> Option<()> {
+    match reader.choice(2, false)? {
+        0 => reader.constrained(0, 97)?,
+        _ => reader.constrained(0, 34)?,
+    };
+    Some(())
+}
+
+fn decode_master_information_block(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let mib = root.child("masterInformationBlock");
+    let bandwidth = enumerated(reader, &DL_BANDWIDTHS, false)?;
+    mib.push(L3Field::leaf("dl-Bandwidth", bandwidth));
+    let phich = mib.child("phich-Config");
+    phich.push(L3Field::leaf(
+        "phich-Duration",
+        enumerated(reader, &PHICH_DURATIONS, false)?,
+    ));
+    phich.push(L3Field::leaf(
+        "phich-Resource",
+        enumerated(reader, &PHICH_RESOURCES, false)?,
+    ));
+    mib.push(L3Field::leaf("systemFrameNumber", bit_string(reader, 8)?));
+    Some(())
+}
+
+fn decode_bcch_dl_sch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, message) = message_type(reader, root, &BCCH_DL_SCH_MESSAGES)?;
+    match index {
+        0 => {
+            if !critical_extensions(reader, None)? {
+                return Some(());
+            }
+            reader.sequence(false, 1)?;
+            let list = message.child("sib-TypeAndInfo");
+            reader.size(1, 32)?;
+            // The SIBs are not decoded, so only the type of the first one
+            // can be read.
+            let sib = reader.choice(10, true)?;
+            list.push(L3Field::new(&format!("sib{}", sib + 2)));
+        }
+        1 => system_information_block_type1(reader, message)?,
+        _ => {}
+    }
+    Some(())
+}
+
+fn system_information_block_type1(reader: &mut PerReader, message: &mut L3Field) -> Option<()> {
+    let (_, present) = reader.sequence(false, 3)?;
+
+    let access = message.child("cellAccessRelatedInfo");
+    let (_, access_present) = reader.sequence(false, 1)?;
+    let plmns = access.child("plmn-IdentityList");
+    for _ in 0..reader.size(1, 6)? {
+        let info = plmns.child("PLMN-IdentityInfo");
+        plmn_identity(reader, info)?;
+        let reserved = enumerated(reader, &["reserved", "notReserved"], false)?;
+        info.push(L3Field::leaf("cellReservedForOperatorUse", reserved));
+    }
+    access.push(L3Field::leaf("trackingAreaCode", bit_string(reader, 16)?));
+    access.push(L3Field::leaf("cellIdentity", bit_string(reader, 28)?));
+    let barred = enumerated(reader, &["barred", "notBarred"], false)?;
+    access.push(L3Field::leaf("cellBarred", barred));
+    let reselection = enumerated(reader, &["allowed", "notAllowed"], false)?;
+    access.push(L3Field::leaf("intraFreqReselection", reselection));
+    access.push(L3Field::leaf("csg-Indication", reader.bit()?));
+    if access_present[0] {
+        access.push(L3Field::leaf("csg-Identity", bit_string(reader, 27)?));
+    }
+
+    let selection = message.child("cellSelectionInfo");
+    let (_, selection_present) = reader.sequence(false, 1)?;
+    selection.push(L3Field::leaf("q-RxLevMin", reader.constrained(-70, -22)?));
+    if selection_present[0] {
+        selection.push(L3Field::leaf("q-RxLevMinOffset", reader.constrained(1, 8)?));
+    }
+    if present[0] {
+        message.push(L3Field::leaf("p-Max", reader.constrained(-30, 33)?));
+    }
+    message.push(L3Field::leaf(
+        "freqBandIndicator",
+        reader.constrained(1, 64)?,
+    ));
+    Some(())
+}
+
+fn decode_pcch(reader: &mut PerReader, root: &mut L3Field) -> Option<()> {
+    let (index, paging) = message_type(reader, root, &PCCH_MESSAGES)?;
+    if index != 0 {
+        return Some(());
+    }
+    let (_, present) = reader.sequence(false, 4)?;
+    if present[0] {
+        let list = paging.child("pagingRecordList");
+        for _ in 0..reader.size(1, 16)? {
+            let record = list.child("PagingRecord");
+            let (extended, _) = reader.sequence(true, 0)?;
+            let identity = record.child("ue-Identity");
+            match reader.choice(2, true)? {
+                0 => s_tmsi(reader, identity)?,
+                1 => identity.push(L3Field::leaf("imsi", digits(reader, 6, 21)?)),
+                _ => return Some(()),
+            }
+            record.push(L3Field::leaf(
+                "cn-Domain",
+                enumerated(reader, &["ps", "cs"], false)?,
+            ));
+            if extended {
+                reader.skip_extensions()?;
+            }
+        }
+    }
+    if present[1] {
+        paging.push(L3Field::leaf("systemInfoModification", "true"));
+    }
+    if present[2] {
+        paging.push(L3Field::leaf("etws-Indication", "true"));
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_connection_request() {
+        // c1, rrcConnectionRequest, r8, s-TMSI (mmec 0x1a, m-TMSI
+        // 0xc0ffee01), mo-Signalling, spare
+        let message = decode(RrcChannel::UlCcch, &[0x41, 0xac, 0x0f, 0xfe, 0xe0, 0x16]);
+        assert_eq!(
+            message.flatten(),
+            vec![
+                (
+                    "UL-CCCH-Message.rrcConnectionRequest.ue-Identity.s-TMSI.mmec".to_string(),
+                    "1a".to_string()
+                ),
+                (
+                    "UL-CCCH-Message.rrcConnectionRequest.ue-Identity.s-TMSI.m-TMSI".to_string(),
+                    "c0ffee01".to_string()
+                ),
+                (
+                    "UL-CCCH-Message.rrcConnectionRequest.establishmentCause".to_string(),
+                    "mo-Signalling".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_measurement_report() {
+        // measId 1, PCell RSRP 50 / RSRQ 20, one EUTRA neighbour with PCI
+        // 100 and RSRP 40
+        let message = decode(
+            RrcChannel::UlDcch,
+            &[0x08, 0x10, 0x32, 0x50, 0x01, 0x91, 0x28],
+        );
+        let results = message.find("measResults").unwrap();
+        assert_eq!(results.find("measId").unwrap().value.as_deref(), Some("1"));
+        let neighbour = results.find("MeasResultEUTRA").unwrap();
+        assert_eq!(
+            neighbour.find("physCellId").unwrap().value.as_deref(),
+            Some("100")
+        );
+        assert_eq!(
+            neighbour.find("rsrpResult").unwrap().value.as_deref(),
+            Some("40")
+        );
+        assert_eq!(message.find("error"), None);
+
+        let truncated = decode(RrcChannel::UlDcch, &[0x08, 0x10]);
+        assert_eq!(
+            truncated.find("error").unwrap().value.as_deref(),
+            Some("message truncated")
+        );
+    }
+
+    #[test]
+    fn test_decode_meas_config() {
+        // rrcConnectionReconfiguration with measObject 2 on EARFCN 1850,
+        // reportConfig 1 for event A3 and measId 1 linking them
+        let message = decode(
+            RrcChannel::DlDcch,
+            &[
+                0x20, 0x10, 0x15, 0x00, 0x02, 0x00, 0x03, 0x9d, 0x5a, 0x00, 0x02, 0x80, 0x24, 0x19,
+                0x70, 0x00, 0x20,
+            ],
+        );
+        let config = message.find("measConfig").unwrap();
+        let object = config.find("MeasObjectToAddMod").unwrap();
+        assert_eq!(
+            object.find("measObjectId").unwrap().value.as_deref(),
+            Some("2")
+        );
+        assert_eq!(
+            object.find("carrierFreq").unwrap().value.as_deref(),
+            Some("1850")
+        );
+        assert_eq!(
+            config.find("eventId").unwrap().value.as_deref(),
+            Some("eventA3")
+        );
+        let identity = config.find("MeasIdToAddMod").unwrap();
+        assert_eq!(identity.find("measId").unwrap().value.as_deref(), Some("1"));
+        assert_eq!(
+            identity.find("measObjectId").unwrap().value.as_deref(),
+            Some("2")
+        );
+        assert_eq!(message.find("error"), None);
+    }
+}
@@ -0,0 +1,618 @@
+use super::l3::L3Field;
+use super::nas;
+use super::per::PerReader;
+
+const PDU_TYPES: [&str; 3] = [
+    "initiatingMessage",
+    "successfulOutcome",
+    "unsuccessfulOutcome",
+];
+
+/// Messages of each S1AP procedure, indexed by procedure code: initiating,
+/// successful and unsuccessful outcome.
+const PROCEDURES: [[&str; 3]; 43] = [
+    [
+        "HandoverRequired",
+        "HandoverCommand",
+        "HandoverPreparationFailure",
+    ],
+    [
+        "HandoverRequest",
+        "HandoverRequestAcknowledge",
+        "HandoverFailure",
+    ],
+    ["HandoverNotify", "", ""],
+    [
+        "PathSwitchRequest",
+        "PathSwitchRequestAcknowledge",
+        "PathSwitchRequestFailure",
+    ],
+    ["HandoverCancel", "HandoverCancelAcknowledge", ""],
+    ["E-RABSetupRequest", "E-RABSetupResponse", ""],
+    ["E-RABModifyRequest", "E-RABModifyResponse", ""],
+    ["E-RABReleaseCommand", "E-RABReleaseResponse", ""],
+    ["E-RABReleaseIndication", "", ""],
+    [
+        "InitialContextSetupRequest",
+        "InitialContextSetupResponse",
+        "InitialContextSetupFailure",
+    ],
+    ["Paging", "", ""],
+    ["DownlinkNASTransport", "", ""],
+    ["InitialUEMessage", "", ""],
+    ["UplinkNASTransport", "", ""],
+    ["Reset", "ResetAcknowledge", ""],
+    ["ErrorIndication", "", ""],
+    ["NASNonDeliveryIndication", "", ""],
+    ["S1SetupRequest", "S1SetupResponse", "S1SetupFailure"],
+    ["UEContextReleaseRequest", "", ""],
+    ["DownlinkS1cdma2000tunnelling", "", ""],
+    ["UplinkS1cdma2000tunnelling", "", ""],
+    [
+        "UEContextModificationRequest",
+        "UEContextModificationResponse",
+        "UEContextModificationFailure",
+    ],
+    ["UECapabilityInfoIndication", "", ""],
+    ["UEContextReleaseCommand", "UEContextReleaseComplete", ""],
+    ["ENBStatusTransfer", "", ""],
+    ["MMEStatusTransfer", "", ""],
+    ["DeactivateTrace", "", ""],
+    ["TraceStart", "", ""],
+    ["TraceFailureIndication", "", ""],
+    [
+        "ENBConfigurationUpdate",
+        "ENBConfigurationUpdateAcknowledge",
+        "ENBConfigurationUpdateFailure",
+    ],
+    [
+        "MMEConfigurationUpdate",
+        "MMEConfigurationUpdateAcknowledge",
+        "MMEConfigurationUpdateFailure",
+    ],
+    ["LocationReportingControl", "", ""],
+    ["LocationReportingFailureIndication", "", ""],
+    ["LocationReport", "", ""],
+    ["OverloadStart", "", ""],
+    ["OverloadStop", "", ""],
+    [
+        "WriteReplaceWarningRequest",
+        "WriteReplaceWarningResponse",
+        "",
+    ],
+    ["ENBDirectInformationTransfer", "", ""],
+    ["MMEDirectInformationTransfer", "", ""],
+    ["PrivateMessage", "", ""],
+    ["ENBConfigurationTransfer", "", ""],
+    ["MMEConfigurationTransfer", "", ""],
+    ["CellTrafficTrace", "", ""],
+];
+
+const RADIO_NETWORK_CAUSES: [&str; 36] = [
+    "unspecified",
+    "tx2relocoverall-expiry",
+    "successful-handover",
+    "release-due-to-eutran-generated-reason",
+    "handover-cancelled",
+    "partial-handover",
+    "ho-failure-in-target-EPC-eNB-or-target-system",
+    "ho-target-not-allowed",
+    "tS1relocoverall-expiry",
+    "tS1relocprep-expiry",
+    "cell-not-available",
+    "unknown-targetID",
+    "no-radio-resources-available-in-target-cell",
+    "unknown-mme-ue-s1ap-id",
+    "unknown-enb-ue-s1ap-id",
+    "unknown-pair-ue-s1ap-id",
+    "handover-desirable-for-radio-reason",
+    "time-critical-handover",
+    "resource-optimisation-handover",
+    "reduce-load-in-serving-cell",
+    "user-inactivity",
+    "radio-connection-with-ue-lost",
+    "load-balancing-tau-required",
+    "cs-fallback-triggered",
+    "ue-not-available-for-ps-service",
+    "radio-resources-not-available",
+    "failure-in-radio-interface-procedure",
+    "invalid-qos-combination",
+    "interrat-redirection",
+    "interaction-with-other-procedure",
+    "unknown-E-RAB-ID",
+    "multiple-E-RAB-ID-instances",
+    "encryption-and-or-integrity-protection-algorithms-not-supported",
+    "s1-intra-system-handover-triggered",
+    "s1-inter-system-handover-triggered",
+    "x2-handover-triggered",
+];
+const RADIO_NETWORK_CAUSE_EXTENSIONS: [&str; 4] = [
+    "redirection-towards-1xRTT",
+    "not-supported-QCI-value",
+    "invalid-CSG-Id",
+    "release-due-to-pre-emption",
+];
+pub const TRANSPORT_CAUSES: [&str; 2] = ["transport-resource-unavailable", "unspecified"];
+const NAS_CAUSES: [&str; 4] = [
+    "normal-release",
+    "authentication-failure",
+    "detach",
+    "unspecified",
+];
+const NAS_CAUSE_EXTENSIONS: [&str; 2] = ["csg-subscription-expiry", "uE-not-in-PLMN-serving-area"];
+pub const PROTOCOL_CAUSES: [&str; 7] = [
+    "transfer-syntax-error",
+    "abstract-syntax-error-reject",
+    "abstract-syntax-error-ignore-and-notify",
+    "message-not-compatible-with-receiver-state",
+    "semantic-error",
+    "abstract-syntax-error-falsely-constructed-message",
+    "unspecified",
+];
+const MISC_CAUSES: [&str; 6] = [
+    "control-processing-overload",
+    "not-enough-user-plane-processing-resources",
+    "hardware-failure",
+    "om-intervention",
+    "unspecified",
+    "unknown-PLMN",
+];
+
+/// Alternatives of a `Cause` choice: the group name with its root and
+/// extension values.
+pub type CauseGroup<'a> = (&'a str, &'a [&'a str], &'a [&'a str]);
+
+const CAUSE_GROUPS: [CauseGroup; 5] = [
+    (
+        "radioNetwork",
+        &RADIO_NETWORK_CAUSES,
+        &RADIO_NETWORK_CAUSE_EXTENSIONS,
+    ),
+    ("transport", &TRANSPORT_CAUSES, &[]),
+    ("nas", &NAS_CAUSES, &NAS_CAUSE_EXTENSIONS),
+    ("protocol", &PROTOCOL_CAUSES, &[]),
+    ("misc", &MISC_CAUSES, &[]),
+];
+
+const HANDOVER_TYPES: [&str; 5] = [
+    "intralte",
+    "ltetoutran",
+    "ltetogeran",
+    "utrantolte",
+    "gerantolte",
+];
+const ESTABLISHMENT_CAUSES: [&str; 5] = [
+    "emergency",
+    "highPriorityAccess",
+    "mt-Access",
+    "mo-Signalling",
+    "mo-Data",
+];
+const ESTABLISHMENT_CAUSE_EXTENSIONS: [&str; 3] =
+    ["delay-TolerantAccess", "mo-VoiceCall", "mo-ExceptionData"];
+const TIMES_TO_WAIT: [&str; 6] = ["v1s", "v2s", "v5s", "v10s", "v20s", "v60s"];
+const TARGET_IDS: [&str; 3] = ["targeteNB-ID", "targetRNC-ID", "cGI"];
+
+/// E-RAB list items: IE id, name, number of optional components and what
+/// follows the E-RAB ID.
+const E_RAB_ITEMS: [(u16, &str, usize, ERabItem); 11] = [
+    (14, "E-RABDataForwardingItem", 5, ERabItem::Id),
+    (15, "E-RABReleaseItemBearerRelComp", 1, ERabItem::Id),
+    (17, "E-RABToBeSetupItemBearerSUReq", 1, ERabItem::QoS),
+    (20, "E-RABAdmittedItem", 5, ERabItem::Id),
+    (21, "E-RABFailedtoSetupItemHOReqAck", 1, ERabItem::Cause),
+    (27, "E-RABToBeSetupItemHOReq", 1, ERabItem::Id),
+    (35, "E-RABItem", 1, ERabItem::Cause),
+    (39, "E-RABSetupItemBearerSURes", 1, ERabItem::Id),
+    (49, "E-RABReleaseItemHOCmd", 5, ERabItem::Id),
+    (50, "E-RABSetupItemCtxtSURes", 1, ERabItem::Id),
+    (52, "E-RABToBeSetupItemCtxtSUReq", 2, ERabItem::QoS),
+];
+
+const E_RAB_LISTS: [(u16, &str); 14] = [
+    (12, "E-RABSubjecttoDataForwardingList"),
+    (13, "E-RABtoReleaseListHOCmd"),
+    (16, "E-RABToBeSetupListBearerSUReq"),
+    (18, "E-RABAdmittedList"),
+    (19, "E-RABFailedToSetupListHOReqAck"),
+    (24, "E-RABToBeSetupListCtxtSUReq"),
+    (28, "E-RABSetupListBearerSURes"),
+    (29, "E-RABFailedToSetupListBearerSURes"),
+    (33, "E-RABToBeReleasedList"),
+    (34, "E-RABFailedToReleaseList"),
+    (48, "E-RABFailedToSetupListCtxtSURes"),
+    (51, "E-RABSetupListCtxtSURes"),
+    (53, "E-RABToBeSetupListHOReq"),
+    (69, "E-RABReleaseListBearerRelComp"),
+];
+
+/// Names of the IEs that are shown undecoded.
+const OTHER_IES: [(u16, &str); 13] = [
+    (3, "SourceID"),
+    (25, "TraceActivation"),
+    (40, "SecurityContext"),
+    (41, "HandoverRestrictionList"),
+    (58, "CriticalityDiagnostics"),
+    (59, "Global-ENB-ID"),
+    (66, "uEaggregateMaximumBitrate"),
+    (73, "SecurityKey"),
+    (74, "UERadioCapability"),
+    (75, "GUMMEI-ID"),
+    (104, "Source-ToTarget-TransparentContainer"),
+    (107, "UESecurityCapabilities"),
+    (123, "Target-ToSource-TransparentContainer"),
+];
+
+#[derive(Clone, Copy)]
+pub enum ERabItem {
+    /// Only the E-RAB ID is decoded.
+    Id,
+    /// The E-RAB ID is followed by the E-RAB level QoS parameters.
+    QoS,
+    /// The E-RAB ID is followed by a cause.
+    Cause,
+}
+
+/// Decodes an S1AP PDU (3GPP TS 36.413).
+pub fn decode(contents: &[u8]) -> L3Field {
+    decode_pdu("S1AP-PDU", contents, message_name, decode_ie)
+}
+
+fn message_name(procedure: usize, pdu_type: usize) -> Option<&'static str> {
+    PROCEDURES
+        .get(procedure)
+        .map(|messages| messages[pdu_type])
+        .filter(|name| !name.is_empty())
+}
+
+/// Decodes the PDU framing shared by S1AP and X2AP: the PDU type, the
+/// procedure code and the list of protocol IEs, each decoded on its own with
+/// `decode_ie` so that one IE we cannot decode does not hide the others.
+pub fn decode_pdu(
+    root_name: &str,
+    contents: &[u8],
+    message_name: fn(usize, usize) -> Option<&'static str>,
+    decode_ie: fn(u16, &[u8]) -> L3Field,
+) -> L3Field {
+    let mut root = L3Field::new(root_name);
+    if decode_message(&mut root, contents, message_name, decode_ie).is_none() {
+        root.push(L3Field::leaf("error", "message truncated"));
+    }
+    root
+}
+
+fn decode_message(
+    root: &mut L3Field,
+    contents: &[u8],
+    message_name: fn(usize, usize) -> Option<&'static str>,
+    decode_ie: fn(u16, &[u8]) -> L3Field,
+) -> Option<()> {
+    let mut reader = PerReader::aligned(contents);
+    let pdu_type = reader.choice(PDU_TYPES.len(), true)?;
+    if pdu_type >= PDU_TYPES.len() {
+        root.push(L3Field::new("extension"));
+        return Some(());
+    }
+    let pdu = root.child(PDU_TYPES[pdu_type]);
+    let procedure = reader.constrained(0, 255)? as usize;
+    reader.enumerated(3, false)?;
+    let value = reader.open_type()?;
+    let message = match message_name(procedure, pdu_type) {
+        Some(name) => pdu.child(name),
+        None => pdu.child(&format!("procedure {}", procedure)),
+    };
+
+    let mut reader = PerReader::aligned(&value);
+    reader.sequence(true, 0)?;
+    for _ in 0..reader.size(0, 65535)? {
+        let (id, value) = protocol_ie(&mut reader)?;
+        message.push(decode_ie(id, &value));
+    }
+    Some(())
+}
+
+/// Reads a protocol IE field and returns its id and encoded value.
+pub fn protocol_ie(reader: &mut PerReader) -> Option<(u16, Vec<u8>)> {
+    let id = reader.constrained(0, 65535)? as u16;
+    reader.enumerated(3, false)?;
+    Some((id, reader.open_type()?))
+}
+
+fn decode_ie(id: u16, value: &[u8]) -> L3Field {
+    let mut reader = PerReader::aligned(value);
+    let field = match id {
+        0 => mme_ue_s1ap_id(&mut reader, "MME-UE-S1AP-ID"),
+        1 => enumerated(&mut reader, &HANDOVER_TYPES, &[])
+            .map(|value| L3Field::leaf("HandoverType", value)),
+        2 => cause(&mut reader, &CAUSE_GROUPS),
+        4 => reader
+            .choice(TARGET_IDS.len(), true)
+            .map(|index| L3Field::leaf("TargetID", TARGET_IDS.get(index).unwrap_or(&"extension"))),
+        8 => enb_ue_s1ap_id(&mut reader, "eNB-UE-S1AP-ID"),
+        26 => reader
+            .octet_string()
+            .map(|contents| nas::nas_field("NAS-PDU", &contents)),
+        65 => enumerated(&mut reader, &TIMES_TO_WAIT, &[])
+            .map(|value| L3Field::leaf("TimeToWait", value)),
+        67 => tai(&mut reader),
+        96 => s_tmsi(&mut reader),
+        99 => ue_s1ap_ids(&mut reader),
+        100 => ecgi(&mut reader, "EUTRAN-CGI"),
+        134 => enumerated(
+            &mut reader,
+            &ESTABLISHMENT_CAUSES,
+            &ESTABLISHMENT_CAUSE_EXTENSIONS,
+        )
+        .map(|value| L3Field::leaf("RRC-Establishment-Cause", value)),
+        _ => {
+            if let Some((_, name)) = E_RAB_LISTS.iter().find(|(list, _)| *list == id) {
+                e_rab_list(&mut reader, name, e_rab_item)
+            } else {
+                return undecoded_ie(id, value, &OTHER_IES);
+            }
+        }
+    };
+    field.unwrap_or_else(|| undecoded_ie(id, value, &OTHER_IES))
+}
+
+/// An IE shown as hex, named after `names` when it is listed there.
+pub fn undecoded_ie(id: u16, value: &[u8], names: &[(u16, &str)]) -> L3Field {
+    let name = match names.iter().find(|(known, _)| *known == id) {
+        Some((_, name)) => name.to_string(),
+        None => format!("id-{}", id),
+    };
+    L3Field::leaf(&name, hex::encode(value))
+}
+
+fn e_rab_item(id: u16, value: &[u8]) -> L3Field {
+    match E_RAB_ITEMS.iter().find(|(item, ..)| *item == id) {
+        Some((_, name, optionals, contents)) => {
+            decode_e_rab_item(value, name, *optionals, *contents, &CAUSE_GROUPS)
+        }
+        None => undecoded_ie(id, value, &[]),
+    }
+}
+
+/// Extensible ENUMERATED with named root and extension values.
+pub fn enumerated(reader: &mut PerReader, root: &[&str], extensions: &[&str]) -> Option<String> {
+    let index = reader.enumerated(root.len(), true)?;
+    Some(match root.get(index) {
+        Some(name) => name.to_string(),
+        None => match extensions.get(index - root.len()) {
+            Some(name) => name.to_string(),
+            None => format!("extension {}", index - root.len()),
+        },
+    })
+}
+
+/// Reads a `Cause` choice into a `Cause` node holding the group and value.
+pub fn cause(reader: &mut PerReader, groups: &[CauseGroup]) -> Option<L3Field> {
+    let mut field = L3Field::new("Cause");
+    let group = reader.choice(groups.len(), true)?;
+    match groups.get(group) {
+        Some((name, root, extensions)) => {
+            field.push(L3Field::leaf(name, enumerated(reader, root, extensions)?))
+        }
+        None => field.push(L3Field::new("extension")),
+    }
+    Some(field)
+}
+
+/// Group and value names of an S1AP cause given as a group, such as the CTR
+/// `EVENT_VALUE_RADIO_NETWORK`, and the value's index in that group or its
+/// name.
+pub fn cause_names(group: &str, value: &str) -> Option<(&'static str, &'static str)> {
+    let group = group.trim_start_matches("EVENT_VALUE_").replace('_', "");
+    let (name, root, extensions) = CAUSE_GROUPS
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(&group))?;
+    let mut causes = root.iter().chain(extensions.iter());
+    let cause = match value.parse::<usize>() {
+        Ok(index) => causes.nth(index),
+        Err(_) => causes.find(|cause| **cause == value),
+    }?;
+    Some((name, cause))
+}
+
+fn mme_ue_s1ap_id(reader: &mut PerReader, name: &str) -> Option<L3Field> {
+    Some(L3Field::leaf(name, reader.constrained(0, 4294967295)?))
+}
+
+fn enb_ue_s1ap_id(reader: &mut PerReader, name: &str) -> Option<L3Field> {
+    Some(L3Field::leaf(name, reader.constrained(0, 16777215)?))
+}
+
+fn ue_s1ap_ids(reader: &mut PerReader) -> Option<L3Field> {
+    let mut field = L3Field::new("UE-S1AP-IDs");
+    match reader.choice(2, true)? {
+        0 => {
+            let pair = field.child("uE-S1AP-ID-pair");
+            reader.sequence(true, 1)?;
+            pair.push(mme_ue_s1ap_id(reader, "mME-UE-S1AP-ID")?);
+            pair.push(enb_ue_s1ap_id(reader, "eNB-UE-S1AP-ID")?);
+        }
+        1 => field.push(mme_ue_s1ap_id(reader, "mME-UE-S1AP-ID")?),
+        _ => field.push(L3Field::new("extension")),
+    }
+    Some(field)
+}
+
+fn plmn_identity(reader: &mut PerReader) -> Option<String> {
+    Some(tbcd_plmn(&reader.octets(3)?))
+}
+
+/// PLMN identity encoded in TBCD, as `MCC-MNC`; the filler digit of a two
+/// digit MNC is left out.
+pub fn tbcd_plmn(octets: &[u8]) -> String {
+    let digit = |octet: u8, high: bool| {
+        let value = if high { octet >> 4 } else { octet & 0x0f };
+        match value {
+            0..=9 => Some((b'0' + value) as char),
+            _ => None,
+        }
+    };
+    let mcc: String = [
+        digit(octets[0], false),
+        digit(octets[0], true),
+        digit(octets[1], false),
+    ]
+    .iter()
+    .flatten()
+    .collect();
+    let mnc: String = [
+        digit(octets[2], false),
+        digit(octets[2], true),
+        digit(octets[1], true),
+    ]
+    .iter()
+    .flatten()
+    .collect();
+    format!("{}-{}", mcc, mnc)
+}
+
+/// E-UTRAN cell global identity: PLMN identity and 28 bit cell identity.
+pub fn ecgi(reader: &mut PerReader, name: &str) -> Option<L3Field> {
+    let mut field = L3Field::new(name);
+    reader.sequence(true, 1)?;
+    field.push(L3Field::leaf("pLMNidentity", plmn_identity(reader)?));
+    let cell = reader.bit_string(28)?;
+    field.push(L3Field::leaf("cell-ID", format!("{:07x}", cell)));
+    Some(field)
+}
+
+fn tai(reader: &mut PerReader) -> Option<L3Field> {
+    let mut field = L3Field::new("TAI");
+    reader.sequence(true, 1)?;
+    field.push(L3Field::leaf("pLMNidentity", plmn_identity(reader)?));
+    field.push(L3Field::leaf("tAC", hex::encode(reader.octets(2)?)));
+    Some(field)
+}
+
+fn s_tmsi(reader: &mut PerReader) -> Option<L3Field> {
+    let mut field = L3Field::new("S-TMSI");
+    reader.sequence(true, 1)?;
+    field.push(L3Field::leaf("mMEC", hex::encode(reader.octets(1)?)));
+    field.push(L3Field::leaf("m-TMSI", hex::encode(reader.octets(4)?)));
+    Some(field)
+}
+
+/// E-RAB list: a list of single IE containers, each holding one item.
+pub fn e_rab_list(
+    reader: &mut PerReader,
+    name: &str,
+    decode_item: fn(u16, &[u8]) -> L3Field,
+) -> Option<L3Field> {
+    let mut field = L3Field::new(name);
+    for _ in 0..reader.size(1, 256)? {
+        let (id, value) = protocol_ie(reader)?;
+        field.push(decode_item(id, &value));
+    }
+    Some(field)
+}
+
+/// Decodes the start of an E-RAB item: the E-RAB ID and, depending on the
+/// item, the QCI and allocation and retention priority or the cause.
+pub fn decode_e_rab_item(
+    value: &[u8],
+    name: &str,
+    optionals: usize,
+    contents: ERabItem,
+    cause_groups: &[CauseGroup],
+) -> L3Field {
+    let mut field = L3Field::new(name);
+    let mut reader = PerReader::aligned(value);
+    if e_rab_item_fields(&mut reader, &mut field, optionals, contents, cause_groups).is_none() {
+        field.push(L3Field::leaf("error", "item truncated"));
+    }
+    field
+}
+
+fn e_rab_item_fields(
+    reader: &mut PerReader,
+    field: &mut L3Field,
+    optionals: usize,
+    contents: ERabItem,
+    cause_groups: &[CauseGroup],
+) -> Option<()> {
+    reader.sequence(true, optionals)?;
+    let id = reader.extensible_constrained(0, 15)?;
+    field.push(L3Field::leaf("e-RAB-ID", id));
+    match contents {
+        ERabItem::Id => {}
+        ERabItem::QoS => {
+            let qos = field.child("e-RABlevelQoSParameters");
+            reader.sequence(true, 2)?;
+            qos.push(L3Field::leaf("qCI", reader.constrained(0, 255)?));
+            let arp = qos.child("allocationRetentionPriority");
+            reader.sequence(true, 1)?;
+            arp.push(L3Field::leaf("priorityLevel", reader.constrained(0, 15)?));
+        }
+        ERabItem::Cause => field.push(cause(reader, cause_groups)?),
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ue_context_release_command() {
+        let message = decode(&[
+            0x00, 0x17, 0x40, 0x11, 0x00, 0x00, 0x02, 0x00, 0x63, 0x00, 0x04, 0x00, 0x01, 0x00,
+            0x05, 0x00, 0x02, 0x40, 0x02, 0x02, 0x80,
+        ]);
+        assert_eq!(
+            message.flatten(),
+            vec![
+                (
+                    "S1AP-PDU.initiatingMessage.UEContextReleaseCommand.UE-S1AP-IDs.uE-S1AP-ID-pair.mME-UE-S1AP-ID".to_string(),
+                    "1".to_string()
+                ),
+                (
+                    "S1AP-PDU.initiatingMessage.UEContextReleaseCommand.UE-S1AP-IDs.uE-S1AP-ID-pair.eNB-UE-S1AP-ID".to_string(),
+                    "5".to_string()
+                ),
+                (
+                    "S1AP-PDU.initiatingMessage.UEContextReleaseCommand.Cause.radioNetwork".to_string(),
+                    "user-inactivity".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_e_rab_item() {
+        // e-RAB-ID 5, qCI 9, priority level 15
+        let item = decode_e_rab_item(
+            &[0x05, 0x00, 0x09, 0x3c],
+            "E-RABToBeSetupItemCtxtSUReq",
+            2,
+            ERabItem::QoS,
+            &CAUSE_GROUPS,
+        );
+        assert_eq!(item.find("e-RAB-ID").unwrap().value.as_deref(), Some("5"));
+        assert_eq!(item.find("qCI").unwrap().value.as_deref(), Some("9"));
+        assert_eq!(
+            item.find("priorityLevel").unwrap().value.as_deref(),
+            Some("15")
+        );
+    }
+
+    #[test]
+    fn test_cause_names() {
+        assert_eq!(
+            cause_names("EVENT_VALUE_RADIO_NETWORK", "21"),
+            Some(("radioNetwork", "radio-connection-with-ue-lost"))
+        );
+        assert_eq!(
+            cause_names("EVENT_VALUE_RADIO_NETWORK", "39"),
+            Some(("radioNetwork", "release-due-to-pre-emption"))
+        );
+        assert_eq!(
+            cause_names("radioNetwork", "user-inactivity"),
+            Some(("radioNetwork", "user-inactivity"))
+        );
+        assert_eq!(cause_names("EVENT_VALUE_NAS", "9"), None);
+    }
+}
@@ -0,0 +1,197 @@
+use super::xml_parser::{Event, Paramter};
+use std::collections::HashMap;
+
+const FIRST_PARAMETER_OFFSET: i64 = 3;
+
+#[derive(Debug, PartialEq)]
+pub struct EventSummary {
+    pub id: u16,
+    pub name: String,
+    pub parameter_count: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParameterLayout {
+    pub name: String,
+    pub offset: Option<i64>,
+    pub width: Option<i64>,
+    pub param_type: String,
+    pub enumeration: Vec<(u8, String)>,
+    pub length_reference: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParameterMatch {
+    pub event_id: u16,
+    pub event_name: String,
+    pub parameter: String,
+}
+
+pub fn list_events(events: &HashMap<u16, Event>) -> Vec<EventSummary> {
+    let mut summaries: Vec<EventSummary> = events
+        .values()
+        .map(|event| EventSummary {
+            id: event.id,
+            name: event.name.to_string(),
+            parameter_count: event.parameters.len(),
+        })
+        .collect();
+    summaries.sort_by_key(|summary| summary.id);
+    summaries
+}
+
+/// Looks an event up by numeric id or by exact name.
+pub fn find_event<'a>(events: &'a HashMap<u16, Event>, key: &str) -> Option<&'a Event> {
+    match key.parse::<u16>() {
+        Ok(id) => events.get(&id),
+        Err(_) => events.values().find(|event| event.name == key),
+    }
+}
+
+/// Byte layout of an event record. Offsets are counted from the start of the
+/// record after the length field and become unknown once a parameter with a
+/// length reference has been passed.
+pub fn event_layout(event: &Event) -> Vec<ParameterLayout> {
+    let mut offset = Some(FIRST_PARAMETER_OFFSET);
+    let mut layout = Vec::new();
+
+    for parameter in &event.parameters {
+        let width = parameter_width(parameter);
+        layout.push(ParameterLayout {
+            name: parameter.name.to_string(),
+            offset,
+            width,
+            param_type: parameter.param_type.to_string(),
+            enumeration: sorted_enumeration(parameter),
+            length_reference: length_reference(parameter),
+        });
+        offset = match (offset, width) {
+            (Some(start), Some(width)) => Some(start + width),
+            _ => None,
+        };
+    }
+    layout
+}
+
+/// Maps every parameter name used by an event to its declared type.
+pub fn parameter_types(events: &HashMap<u16, Event>) -> HashMap<String, String> {
+    let mut types = HashMap::new();
+    for event in events.values() {
+        for parameter in &event.parameters {
+            types.insert(parameter.name.to_string(), parameter.param_type.to_string());
+        }
+    }
+    types
+}
+
+/// Case-insensitive substring search for parameter names across all events.
+pub fn search_parameters(events: &HashMap<u16, Event>, pattern: &str) -> Vec<ParameterMatch> {
+    let pattern = pattern.to_uppercase();
+    let mut matches: Vec<ParameterMatch> = Vec::new();
+
+    for event in events.values() {
+        for parameter in &event.parameters {
+            if parameter.name.to_uppercase().contains(&pattern) {
+                matches.push(ParameterMatch {
+                    event_id: event.id,
+                    event_name: event.name.to_string(),
+                    parameter: parameter.name.to_string(),
+                });
+            }
+        }
+    }
+    matches.sort_by(|a, b| {
+        a.parameter
+            .cmp(&b.parameter)
+            .then(a.event_id.cmp(&b.event_id))
+    });
+    matches
+}
+
+fn parameter_width(parameter: &Paramter) -> Option<i64> {
+    match parameter.number_of_bytes {
+        -1 => None,
+        n => Some(n),
+    }
+}
+
+fn length_reference(parameter: &Paramter) -> Option<String> {
+    match parameter.number_of_bytes {
+        -1 => Some(parameter.related_name.to_string()),
+        _ => None,
+    }
+}
+
+fn sorted_enumeration(parameter: &Paramter) -> Vec<(u8, String)> {
+    let mut enumeration: Vec<(u8, String)> = parameter
+        .enumeration
+        .iter()
+        .map(|(id, value)| (*id, value.to_string()))
+        .collect();
+    enumeration.sort_by_key(|(id, _)| *id);
+    enumeration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameter(name: &str, number_of_bytes: i64, related_name: &str) -> Paramter {
+        Paramter {
+            name: name.to_string(),
+            param_type: "UINT".to_string(),
+            number_of_bytes,
+            enumeration: HashMap::new(),
+            related_name: related_name.to_string(),
+        }
+    }
+
+    fn events() -> HashMap<u16, Event> {
+        let event = Event {
+            name: "RRC_CONNECTION_SETUP".to_string(),
+            id: 3,
+            elements: vec![],
+            parameters: vec![
+                parameter("EVENT_PARAM_TIMESTAMP_HOUR", 1, ""),
+                parameter("EVENT_PARAM_L3MESSAGE_LENGTH", 2, ""),
+                parameter(
+                    "EVENT_PARAM_L3MESSAGE_CONTENTS",
+                    -1,
+                    "EVENT_PARAM_L3MESSAGE_LENGTH",
+                ),
+                parameter("EVENT_PARAM_RAC_UE_REF", 4, ""),
+            ],
+        };
+        let mut events = HashMap::new();
+        events.insert(3, event);
+        events
+    }
+
+    #[test]
+    fn test_event_layout() {
+        let events = events();
+        let layout = event_layout(find_event(&events, "RRC_CONNECTION_SETUP").unwrap());
+
+        assert_eq!(layout[0].offset, Some(3));
+        assert_eq!(layout[1].offset, Some(4));
+        assert_eq!(layout[2].offset, Some(6));
+        assert_eq!(layout[2].width, None);
+        assert_eq!(
+            layout[2].length_reference,
+            Some("EVENT_PARAM_L3MESSAGE_LENGTH".to_string())
+        );
+        assert_eq!(layout[3].offset, None);
+    }
+
+    #[test]
+    fn test_search_parameters() {
+        let events = events();
+        let matches = search_parameters(&events, "l3message");
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].parameter, "EVENT_PARAM_L3MESSAGE_CONTENTS");
+        assert_eq!(matches[0].event_id, 3);
+        assert!(find_event(&events, "3").is_some());
+        assert!(find_event(&events, "UNKNOWN").is_none());
+    }
+}
@@ -0,0 +1,272 @@
+use super::xml_parser::{Event, Paramter};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct SchemaDiff {
+    pub added_events: Vec<String>,
+    pub removed_events: Vec<String>,
+    pub changed_ids: Vec<IdChange>,
+    pub events: Vec<EventDiff>,
+    pub parameters: Vec<ParameterDiff>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct IdChange {
+    pub event: String,
+    pub old_id: u16,
+    pub new_id: u16,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct EventDiff {
+    pub event: String,
+    pub added_parameters: Vec<String>,
+    pub removed_parameters: Vec<String>,
+    pub reordered: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ParameterDiff {
+    pub parameter: String,
+    pub old_width: String,
+    pub new_width: String,
+    pub added_values: Vec<(u8, String)>,
+    pub removed_values: Vec<(u8, String)>,
+    pub renamed_values: Vec<(u8, String, String)>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == SchemaDiff::default()
+    }
+}
+
+/// Compares two schemas event by event, matching events by name so that
+/// renumbered events show up as id changes rather than as add/remove pairs.
+pub fn diff_schemas(old: &HashMap<u16, Event>, new: &HashMap<u16, Event>) -> SchemaDiff {
+    let old_events = events_by_name(old);
+    let new_events = events_by_name(new);
+    let mut diff = SchemaDiff::default();
+
+    for (name, new_event) in &new_events {
+        match old_events.get(name) {
+            Some(old_event) => {
+                if old_event.id != new_event.id {
+                    diff.changed_ids.push(IdChange {
+                        event: name.to_string(),
+                        old_id: old_event.id,
+                        new_id: new_event.id,
+                    });
+                }
+                let event_diff = diff_event(old_event, new_event);
+                if event_diff != EventDiff::default() {
+                    diff.events.push(EventDiff {
+                        event: name.to_string(),
+                        ..event_diff
+                    });
+                }
+            }
+            None => diff.added_events.push(name.to_string()),
+        }
+    }
+    for name in old_events.keys() {
+        if !new_events.contains_key(name) {
+            diff.removed_events.push(name.to_string());
+        }
+    }
+
+    let old_parameters = parameters_by_name(old);
+    let new_parameters = parameters_by_name(new);
+    for (name, new_parameter) in &new_parameters {
+        if let Some(old_parameter) = old_parameters.get(name) {
+            let parameter_diff = diff_parameter(old_parameter, new_parameter);
+            if parameter_diff != ParameterDiff::default() {
+                diff.parameters.push(ParameterDiff {
+                    parameter: name.to_string(),
+                    ..parameter_diff
+                });
+            }
+        }
+    }
+    diff
+}
+
+fn diff_event(old: &Event, new: &Event) -> EventDiff {
+    let old_names: Vec<&String> = old.parameters.iter().map(|p| &p.name).collect();
+    let new_names: Vec<&String> = new.parameters.iter().map(|p| &p.name).collect();
+
+    let added_parameters = new_names
+        .iter()
+        .filter(|name| !old_names.contains(name))
+        .map(|name| name.to_string())
+        .collect();
+    let removed_parameters = old_names
+        .iter()
+        .filter(|name| !new_names.contains(name))
+        .map(|name| name.to_string())
+        .collect();
+
+    let old_common: Vec<&&String> = old_names.iter().filter(|n| new_names.contains(n)).collect();
+    let new_common: Vec<&&String> = new_names.iter().filter(|n| old_names.contains(n)).collect();
+
+    EventDiff {
+        event: String::new(),
+        added_parameters,
+        removed_parameters,
+        reordered: old_common != new_common,
+    }
+}
+
+fn diff_parameter(old: &Paramter, new: &Paramter) -> ParameterDiff {
+    let mut diff = ParameterDiff::default();
+
+    let old_width = width_to_string(old);
+    let new_width = width_to_string(new);
+    if old_width != new_width {
+        diff.old_width = old_width;
+        diff.new_width = new_width;
+    }
+
+    let old_values: BTreeMap<&u8, &String> = old.enumeration.iter().collect();
+    let new_values: BTreeMap<&u8, &String> = new.enumeration.iter().collect();
+    for (id, value) in &new_values {
+        match old_values.get(id) {
+            Some(old_value) if old_value != value => {
+                diff.renamed_values
+                    .push((**id, old_value.to_string(), value.to_string()));
+            }
+            Some(_) => {}
+            None => diff.added_values.push((**id, value.to_string())),
+        }
+    }
+    for (id, value) in &old_values {
+        if !new_values.contains_key(id) {
+            diff.removed_values.push((**id, value.to_string()));
+        }
+    }
+    diff
+}
+
+pub fn width_to_string(parameter: &Paramter) -> String {
+    match parameter.number_of_bytes {
+        -1 => format!("var({})", parameter.related_name),
+        n => n.to_string(),
+    }
+}
+
+fn events_by_name(events: &HashMap<u16, Event>) -> BTreeMap<String, &Event> {
+    events
+        .values()
+        .map(|event| (event.name.to_string(), event))
+        .collect()
+}
+
+fn parameters_by_name(events: &HashMap<u16, Event>) -> BTreeMap<String, &Paramter> {
+    let mut parameters = BTreeMap::new();
+    for event in events.values() {
+        for parameter in &event.parameters {
+            parameters.insert(parameter.name.to_string(), parameter);
+        }
+    }
+    parameters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameter(name: &str, number_of_bytes: i64, enumeration: &[(u8, &str)]) -> Paramter {
+        Paramter {
+            name: name.to_string(),
+            param_type: "UINT".to_string(),
+            number_of_bytes,
+            enumeration: enumeration
+                .iter()
+                .map(|(id, value)| (*id, value.to_string()))
+                .collect(),
+            related_name: "".to_string(),
+        }
+    }
+
+    fn schema(definitions: Vec<(&str, u16, Vec<Paramter>)>) -> HashMap<u16, Event> {
+        definitions
+            .into_iter()
+            .map(|(name, id, parameters)| {
+                (
+                    id,
+                    Event {
+                        name: name.to_string(),
+                        id,
+                        elements: vec![],
+                        parameters,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_schemas() {
+        let old = schema(vec![
+            (
+                "EVENT_A",
+                1,
+                vec![
+                    parameter("P1", 1, &[]),
+                    parameter("P2", 2, &[(0, "OFF"), (1, "ON")]),
+                ],
+            ),
+            ("EVENT_B", 2, vec![]),
+        ]);
+        let new = schema(vec![
+            (
+                "EVENT_A",
+                5,
+                vec![
+                    parameter("P2", 2, &[(0, "DISABLED"), (2, "AUTO")]),
+                    parameter("P1", 4, &[]),
+                    parameter("P3", 1, &[]),
+                ],
+            ),
+            ("EVENT_C", 3, vec![]),
+        ]);
+
+        let diff = diff_schemas(&old, &new);
+
+        assert_eq!(diff.added_events, vec!["EVENT_C"]);
+        assert_eq!(diff.removed_events, vec!["EVENT_B"]);
+        assert_eq!(
+            diff.changed_ids,
+            vec![IdChange {
+                event: "EVENT_A".to_string(),
+                old_id: 1,
+                new_id: 5
+            }]
+        );
+        assert_eq!(diff.events[0].added_parameters, vec!["P3"]);
+        assert!(diff.events[0].reordered);
+        assert_eq!(diff.parameters[0].parameter, "P1");
+        assert_eq!(diff.parameters[0].new_width, "4");
+        assert_eq!(
+            diff.parameters[1].renamed_values,
+            vec![(0, "OFF".to_string(), "DISABLED".to_string())]
+        );
+        assert_eq!(
+            diff.parameters[1].added_values,
+            vec![(2, "AUTO".to_string())]
+        );
+        assert_eq!(
+            diff.parameters[1].removed_values,
+            vec![(1, "ON".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_diff_identical_schemas() {
+        let old = schema(vec![("EVENT_A", 1, vec![parameter("P1", 1, &[])])]);
+        let new = schema(vec![("EVENT_A", 1, vec![parameter("P1", 1, &[])])]);
+
+        assert!(diff_schemas(&old, &new).is_empty());
+    }
+}
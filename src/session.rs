@@ -0,0 +1,401 @@
+use super::l3::{cause, decode_event, L3Field};
+use super::s1ap::cause_names;
+use super::trace_reader::TraceEvent;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+pub const RAC_UE_REF: &str = "EVENT_PARAM_RAC_UE_REF";
+pub const ENB_S1AP_ID: &str = "EVENT_PARAM_ENBS1APID";
+pub const MME_S1AP_ID: &str = "EVENT_PARAM_MMES1APID";
+pub const CRNTI: &str = "EVENT_PARAM_CRNTI";
+pub const GLOBAL_CELL_ID: &str = "EVENT_PARAM_GLOBAL_CELL_ID";
+const CAUSE_GROUP: &str = "EVENT_PARAM_3GPP_CAUSE_GROUP";
+const CAUSE: &str = "EVENT_PARAM_3GPP_CAUSE";
+const INTERNAL_RELEASE_CAUSE: &str = "EVENT_PARAM_INTERNAL_RELEASE_CAUSE";
+
+/// Parameters that identify the UE an event belongs to, in the order they
+/// are tried when looking up its session.
+pub const IDENTIFIERS: [&str; 4] = [RAC_UE_REF, ENB_S1AP_ID, MME_S1AP_ID, CRNTI];
+
+/// Events that start a new session for the UE.
+const SETUP_EVENTS: [&str; 3] = [
+    "RRC_RRC_CONNECTION_REQUEST",
+    "RRC_RRC_CONNECTION_REESTABLISHMENT_REQUEST",
+    "INTERNAL_PROC_RRC_CONN_SETUP",
+];
+const HANDOVER_IN_EVENTS: [&str; 4] = [
+    "INTERNAL_PROC_HO_EXEC_X2_IN",
+    "INTERNAL_PROC_HO_EXEC_S1_IN",
+    "X2_HANDOVER_REQUEST",
+    "S1_HANDOVER_REQUEST",
+];
+const HANDOVER_OUT_EVENTS: [&str; 2] = [
+    "INTERNAL_PROC_HO_EXEC_X2_OUT",
+    "INTERNAL_PROC_HO_EXEC_S1_OUT",
+];
+/// Events that end the session, after which its identifiers may be reused.
+const RELEASE_EVENTS: [&str; 1] = ["INTERNAL_PROC_UE_CTXT_RELEASE"];
+/// S1 messages carrying the cause of the release, used when the release
+/// event itself has none.
+const RELEASE_MESSAGES: [&str; 2] = [
+    "S1_UE_CONTEXT_RELEASE_REQUEST",
+    "S1_UE_CONTEXT_RELEASE_COMMAND",
+];
+
+/// How long after its release a session still picks up events for its
+/// identifiers, such as a release complete logged after the release.
+const RELEASE_GRACE_MS: u64 = 1000;
+
+/// The events of one UE from connection setup or handover in until its
+/// context is released. Events are indexes into the slice the sessions
+/// were built from.
+#[derive(Debug, Default, Serialize)]
+pub struct Session {
+    pub id: usize,
+    pub start: u64,
+    pub end: u64,
+    pub rac_ue_refs: Vec<String>,
+    pub enb_s1ap_ids: Vec<String>,
+    pub mme_s1ap_ids: Vec<String>,
+    pub crntis: Vec<String>,
+    pub cells: Vec<String>,
+    pub handover_in: bool,
+    pub handover_out: bool,
+    pub released: bool,
+    pub end_cause: Option<Cause>,
+    pub events: Vec<usize>,
+}
+
+impl Session {
+    fn identifiers_mut(&mut self, name: &str) -> &mut Vec<String> {
+        match name {
+            RAC_UE_REF => &mut self.rac_ue_refs,
+            ENB_S1AP_ID => &mut self.enb_s1ap_ids,
+            MME_S1AP_ID => &mut self.mme_s1ap_ids,
+            _ => &mut self.crntis,
+        }
+    }
+
+    pub fn duration(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+/// The cause of a procedure or release.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cause {
+    /// An S1AP cause, by its group and value names.
+    S1ap(&'static str, &'static str),
+    /// An `EVENT_PARAM_INTERNAL_RELEASE_CAUSE` value.
+    Internal(String),
+    /// A cause that could not be decoded, as found in the event or message.
+    Undecoded(String),
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cause::S1ap(group, value) => write!(f, "{}: {}", group, value),
+            Cause::Internal(cause) | Cause::Undecoded(cause) => write!(f, "{}", cause),
+        }
+    }
+}
+
+impl Serialize for Cause {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// An identifier of a UE: its name, value and the cell it is unique in,
+/// which is empty for all but the C-RNTI.
+type Identifier<'a> = (&'a str, &'a str, &'a str);
+
+/// Groups events into UE sessions. An event joins the open session that
+/// shares any of its identifiers, and links its other identifiers to that
+/// session, so S1 events carrying only the S1AP ids follow the RRC events
+/// carrying the RAC UE reference. A C-RNTI is only unique within a cell, so
+/// it is matched together with the cell of the event and ignored in events
+/// without one.
+pub fn build_sessions(events: &[TraceEvent]) -> Vec<Session> {
+    let mut order: Vec<usize> = (0..events.len()).collect();
+    order.sort_by_key(|&index| events[index].timestamp);
+
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut open: HashMap<Identifier, usize> = HashMap::new();
+    // The keys each session added to `open`, so closing it only touches those.
+    let mut open_keys: Vec<Vec<Identifier>> = Vec::new();
+    let mut released: HashMap<Identifier, usize> = HashMap::new();
+
+    for index in order {
+        let event = &events[index];
+        let cell = event
+            .parameters
+            .iter()
+            .find(|parameter| parameter.name == GLOBAL_CELL_ID)
+            .map(|parameter| parameter.value.as_str());
+        let identifiers: Vec<Identifier> = event
+            .parameters
+            .iter()
+            .filter(|parameter| IDENTIFIERS.contains(&parameter.name.as_str()))
+            .filter(|parameter| !parameter.value.is_empty())
+            .filter_map(|parameter| {
+                let scope = match parameter.name.as_str() {
+                    CRNTI => cell?,
+                    _ => "",
+                };
+                Some((parameter.name.as_str(), parameter.value.as_str(), scope))
+            })
+            .collect();
+        if identifiers.is_empty() {
+            continue;
+        }
+        let starting = SETUP_EVENTS.contains(&event.name.as_str())
+            || HANDOVER_IN_EVENTS.contains(&event.name.as_str());
+
+        let mut current = identifiers.iter().find_map(|key| open.get(key).copied());
+        if let Some(session) = current {
+            if SETUP_EVENTS.contains(&event.name.as_str())
+                && sessions[session]
+                    .events
+                    .iter()
+                    .any(|&earlier| events[earlier].name == event.name)
+            {
+                // A new connection for identifiers still in use: the
+                // previous session ended without a release in the trace.
+                close(session, &mut open, &mut open_keys);
+                current = None;
+            }
+        }
+        if current.is_none() && !starting {
+            current = identifiers
+                .iter()
+                .find_map(|key| released.get(key).copied())
+                .filter(|&session| event.timestamp <= sessions[session].end + RELEASE_GRACE_MS);
+        }
+        let session_index = match current {
+            Some(session) => session,
+            None => {
+                sessions.push(Session {
+                    id: sessions.len() + 1,
+                    start: event.timestamp,
+                    ..Default::default()
+                });
+                open_keys.push(Vec::new());
+                sessions.len() - 1
+            }
+        };
+
+        let session = &mut sessions[session_index];
+        session.events.push(index);
+        session.end = event.timestamp;
+        for &key in &identifiers {
+            let (name, value, _) = key;
+            let known = session.identifiers_mut(name);
+            if !known.iter().any(|known| known == value) {
+                known.push(value.to_string());
+            }
+            if !session.released && open.insert(key, session_index) != Some(session_index) {
+                open_keys[session_index].push(key);
+            }
+        }
+        update_session(session, event);
+
+        if RELEASE_EVENTS.contains(&event.name.as_str()) {
+            session.released = true;
+            close(session_index, &mut open, &mut open_keys);
+            for key in identifiers {
+                released.insert(key, session_index);
+            }
+        }
+    }
+    sessions
+}
+
+fn close<'a>(
+    session: usize,
+    open: &mut HashMap<Identifier<'a>, usize>,
+    open_keys: &mut [Vec<Identifier<'a>>],
+) {
+    for key in open_keys[session].drain(..) {
+        if open.get(&key) == Some(&session) {
+            open.remove(&key);
+        }
+    }
+}
+
+fn update_session(session: &mut Session, event: &TraceEvent) {
+    let name = event.name.as_str();
+    if HANDOVER_IN_EVENTS.contains(&name) && session.events.len() == 1 {
+        session.handover_in = true;
+    }
+    if HANDOVER_OUT_EVENTS.contains(&name) {
+        session.handover_out = true;
+    }
+    if let Some(cell) = event
+        .parameters
+        .iter()
+        .find(|parameter| parameter.name == GLOBAL_CELL_ID)
+    {
+        if session.cells.last() != Some(&cell.value) {
+            session.cells.push(cell.value.to_string());
+        }
+    }
+
+    if RELEASE_EVENTS.contains(&name) {
+        if let Some(found) = event_cause(event) {
+            session.end_cause = Some(found);
+        }
+    } else if RELEASE_MESSAGES.contains(&name) && !session.released {
+        if let Some(found) = decode_event(event).as_ref().and_then(message_cause) {
+            session.end_cause = Some(found);
+        }
+    }
+}
+
+/// The 3GPP cause of a procedure event, or else its internal release cause.
+pub fn event_cause(event: &TraceEvent) -> Option<Cause> {
+    let value = |name: &str| {
+        event
+            .parameters
+            .iter()
+            .find(|parameter| parameter.name == name)
+            .map(|parameter| parameter.value.to_string())
+    };
+    match (value(CAUSE_GROUP), value(CAUSE)) {
+        (Some(group), Some(cause)) => Some(match cause_names(&group, &cause) {
+            Some((group, cause)) => Cause::S1ap(group, cause),
+            None => Cause::Undecoded(format!("{}: {}", group, cause)),
+        }),
+        (None, Some(cause)) => Some(Cause::Undecoded(cause)),
+        _ => value(INTERNAL_RELEASE_CAUSE).map(Cause::Internal),
+    }
+}
+
+/// The cause of a decoded S1 message.
+fn message_cause(message: &L3Field) -> Option<Cause> {
+    let decoded = message.find("Cause").and_then(|cause| {
+        let value = cause.children.first()?;
+        cause_names(&value.name, value.value.as_deref()?)
+    });
+    match decoded {
+        Some((group, cause)) => Some(Cause::S1ap(group, cause)),
+        None => cause(message).map(Cause::Undecoded),
+    }
+}
+
+/// Sessions that include the given RAC UE reference.
+pub fn sessions_with_ueref<'a>(sessions: &'a [Session], ueref: &str) -> Vec<&'a Session> {
+    sessions
+        .iter()
+        .filter(|session| session.rac_ue_refs.iter().any(|known| known == ueref))
+        .collect()
+}
+
+/// Milliseconds since midnight as `HH:MM:SS.mmm`.
+pub fn format_time(timestamp: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        timestamp / 3_600_000,
+        timestamp / 60_000 % 60,
+        timestamp / 1000 % 60,
+        timestamp % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_reader::event;
+
+    #[test]
+    fn test_build_sessions() {
+        let events = vec![
+            event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                100,
+                &[(RAC_UE_REF, "7"), (CRNTI, "1234"), (GLOBAL_CELL_ID, "11")],
+            ),
+            event(
+                "INTERNAL_PROC_INITIAL_CTXT_SETUP",
+                150,
+                &[(RAC_UE_REF, "7"), (ENB_S1AP_ID, "42"), (MME_S1AP_ID, "9")],
+            ),
+            event(
+                "S1_INITIAL_CONTEXT_SETUP_REQUEST",
+                160,
+                &[(ENB_S1AP_ID, "42")],
+            ),
+            event("S1_UE_CONTEXT_RELEASE_COMMAND", 900, &[(MME_S1AP_ID, "9")]),
+            event(
+                "INTERNAL_PROC_UE_CTXT_RELEASE",
+                910,
+                &[
+                    (RAC_UE_REF, "7"),
+                    (ENB_S1AP_ID, "42"),
+                    (CAUSE_GROUP, "radioNetwork"),
+                    (CAUSE, "user-inactivity"),
+                ],
+            ),
+            event(
+                "S1_UE_CONTEXT_RELEASE_COMPLETE",
+                920,
+                &[(ENB_S1AP_ID, "42")],
+            ),
+            event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                2000,
+                &[(RAC_UE_REF, "7"), (GLOBAL_CELL_ID, "12")],
+            ),
+        ];
+        let sessions = build_sessions(&events);
+
+        assert_eq!(sessions.len(), 2);
+        let first = &sessions[0];
+        assert_eq!((first.start, first.end), (100, 920));
+        assert_eq!(first.events, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(first.enb_s1ap_ids, vec!["42"]);
+        assert_eq!(first.cells, vec!["11"]);
+        assert_eq!(
+            first.end_cause,
+            Some(Cause::S1ap("radioNetwork", "user-inactivity"))
+        );
+        assert!(first.released);
+        assert_eq!(sessions[1].events, vec![6]);
+        assert_eq!(sessions_with_ueref(&sessions, "7").len(), 2);
+    }
+
+    #[test]
+    fn test_build_sessions_crnti_per_cell() {
+        let events = vec![
+            event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                100,
+                &[(RAC_UE_REF, "7"), (CRNTI, "1234"), (GLOBAL_CELL_ID, "11")],
+            ),
+            event(
+                "INTERNAL_PROC_HO_EXEC_X2_IN",
+                200,
+                &[(RAC_UE_REF, "8"), (CRNTI, "1234"), (GLOBAL_CELL_ID, "12")],
+            ),
+            event(
+                "RRC_MEASUREMENT_REPORT",
+                300,
+                &[(CRNTI, "1234"), (GLOBAL_CELL_ID, "11")],
+            ),
+            event("RRC_MEASUREMENT_REPORT", 400, &[(CRNTI, "1234")]),
+        ];
+        let sessions = build_sessions(&events);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].events, vec![0, 2]);
+        assert_eq!(sessions[1].events, vec![1]);
+        assert!(sessions[1].handover_in);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(27998874), "07:46:38.874");
+    }
+}
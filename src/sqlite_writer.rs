@@ -0,0 +1,353 @@
+use super::converter::create_converter;
+use super::trace_reader::TraceEvent;
+use super::units::{converted_column, converted_values};
+use super::writer::EventWriter;
+use super::xml_parser::{Event, Paramter};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+const INDEXED_PARAMETERS: [&str; 3] = [
+    "EVENT_PARAM_RAC_UE_REF",
+    "EVENT_PARAM_GLOBAL_CELL_ID",
+    "EVENT_PARAM_CELL_ID",
+];
+
+const CREATE_COMMON_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        id INTEGER PRIMARY KEY,
+        file_id INTEGER NOT NULL REFERENCES files(id),
+        event TEXT NOT NULL,
+        timestamp INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_timestamp ON events(timestamp);
+    CREATE INDEX IF NOT EXISTS events_event ON events(event);
+    CREATE TABLE IF NOT EXISTS schema_events (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS schema_parameters (
+        event_id INTEGER NOT NULL REFERENCES schema_events(id),
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        type TEXT NOT NULL,
+        number_of_bytes INTEGER,
+        length_reference TEXT,
+        PRIMARY KEY (event_id, position)
+    );
+    CREATE TABLE IF NOT EXISTS schema_enums (
+        parameter TEXT NOT NULL,
+        value INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (parameter, value)
+    );
+";
+
+fn to_io_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_affinity(parameter: &Paramter) -> &'static str {
+    match parameter.param_type.to_lowercase().as_str() {
+        "uint" | "long" => "INTEGER",
+        "binary" => "BLOB",
+        _ => "TEXT",
+    }
+}
+
+/// Per-event table: the columns it was created with and the insert statement.
+struct EventTable {
+    columns: Vec<(String, &'static str)>,
+    insert: String,
+}
+
+/// Writes decoded events into a SQLite database with one table per event
+/// type, next to `files`, `events` and `schema_*` metadata tables. Radio
+/// measurements get an extra `<PARAM>_<unit>` column with their value in
+/// the physical unit. Several trace files can be written into the same
+/// database.
+pub struct SqliteWriter {
+    connection: Connection,
+    definitions: HashMap<String, Event>,
+    tables: HashMap<String, EventTable>,
+    file_id: i64,
+}
+
+impl SqliteWriter {
+    pub fn new(
+        path: &str,
+        events_definition: &HashMap<u16, Event>,
+        filename: &str,
+    ) -> io::Result<Self> {
+        let connection = Connection::open(path).map_err(to_io_error)?;
+        connection
+            .execute_batch(CREATE_COMMON_TABLES)
+            .map_err(to_io_error)?;
+        connection.execute_batch("BEGIN").map_err(to_io_error)?;
+
+        write_schema(&connection, events_definition).map_err(to_io_error)?;
+        connection
+            .execute("INSERT INTO files (name) VALUES (?1)", params![filename])
+            .map_err(to_io_error)?;
+        let file_id = connection.last_insert_rowid();
+
+        Ok(Self {
+            connection,
+            definitions: events_definition
+                .values()
+                .map(|event| (event.name.to_string(), event.clone()))
+                .collect(),
+            tables: HashMap::new(),
+            file_id,
+        })
+    }
+
+    fn create_table(&mut self, name: &str) -> rusqlite::Result<()> {
+        let definition = match self.definitions.get(name) {
+            Some(definition) => definition,
+            None => return Ok(()),
+        };
+
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut columns: Vec<(String, &'static str)> = Vec::new();
+        for parameter in definition
+            .parameters
+            .iter()
+            .filter(|p| create_converter(&p.param_type).is_some())
+            .filter(|p| seen.insert(p.name.as_str()))
+        {
+            columns.push((parameter.name.to_string(), column_affinity(parameter)));
+            if let Some(column) = converted_column(&parameter.name) {
+                columns.push((column, "REAL"));
+            }
+        }
+
+        let mut ddl: Vec<String> = vec![
+            "event_id INTEGER PRIMARY KEY REFERENCES events(id)".to_string(),
+            "timestamp INTEGER NOT NULL".to_string(),
+        ];
+        ddl.extend(
+            columns
+                .iter()
+                .map(|(column, affinity)| format!("{} {}", quote(column), affinity)),
+        );
+        self.connection.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} ({});\nCREATE INDEX IF NOT EXISTS {} ON {}(timestamp);",
+            quote(name),
+            ddl.join(", "),
+            quote(&format!("{}_timestamp", name)),
+            quote(name)
+        ))?;
+        for (column, _) in &columns {
+            if INDEXED_PARAMETERS.contains(&column.as_str()) {
+                self.connection.execute_batch(&format!(
+                    "CREATE INDEX IF NOT EXISTS {} ON {}({});",
+                    quote(&format!("{}_{}", name, column)),
+                    quote(name),
+                    quote(column)
+                ))?;
+            }
+        }
+
+        let names: Vec<String> = columns.iter().map(|(column, _)| quote(column)).collect();
+        let placeholders = vec!["?"; columns.len() + 2].join(", ");
+        let insert = format!(
+            "INSERT INTO {} (event_id, timestamp{}{}) VALUES ({})",
+            quote(name),
+            if names.is_empty() { "" } else { ", " },
+            names.join(", "),
+            placeholders
+        );
+        self.tables
+            .insert(name.to_string(), EventTable { columns, insert });
+        Ok(())
+    }
+
+    fn insert(&mut self, event: &TraceEvent) -> rusqlite::Result<()> {
+        if !self.tables.contains_key(&event.name) {
+            self.create_table(&event.name)?;
+        }
+        let table = match self.tables.get(&event.name) {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+
+        self.connection
+            .prepare_cached("INSERT INTO events (file_id, event, timestamp) VALUES (?1, ?2, ?3)")?
+            .execute(params![self.file_id, event.name, event.timestamp as i64])?;
+        let event_id = self.connection.last_insert_rowid();
+
+        let values: HashMap<&str, &str> = event
+            .parameters
+            .iter()
+            .map(|p| (p.name.as_str(), p.value.as_str()))
+            .collect();
+        let converted = converted_values(event);
+        let mut row: Vec<Value> = vec![
+            Value::Integer(event_id),
+            Value::Integer(event.timestamp as i64),
+        ];
+        for (column, affinity) in &table.columns {
+            let value = match converted.get(column) {
+                Some(value) => Some(value.as_str()),
+                None => values.get(column.as_str()).copied(),
+            };
+            row.push(column_value(value, affinity));
+        }
+        self.connection
+            .prepare_cached(&table.insert)?
+            .execute(params_from_iter(row))?;
+        Ok(())
+    }
+}
+
+fn column_value(value: Option<&str>, affinity: &str) -> Value {
+    let value = match value {
+        Some(value) => value,
+        None => return Value::Null,
+    };
+    match affinity {
+        "INTEGER" => match value.parse::<i64>() {
+            Ok(number) => Value::Integer(number),
+            Err(_) => Value::Text(value.to_string()),
+        },
+        "REAL" => match value.parse::<f64>() {
+            Ok(number) => Value::Real(number),
+            Err(_) => Value::Text(value.to_string()),
+        },
+        "BLOB" => match hex::decode(value) {
+            Ok(bytes) => Value::Blob(bytes),
+            Err(_) => Value::Text(value.to_string()),
+        },
+        _ => Value::Text(value.to_string()),
+    }
+}
+
+fn write_schema(
+    connection: &Connection,
+    events_definition: &HashMap<u16, Event>,
+) -> rusqlite::Result<()> {
+    let mut insert_event =
+        connection.prepare("INSERT OR REPLACE INTO schema_events (id, name) VALUES (?1, ?2)")?;
+    let mut insert_parameter = connection.prepare(
+        "INSERT OR REPLACE INTO schema_parameters
+         (event_id, position, name, type, number_of_bytes, length_reference)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut insert_enum = connection.prepare(
+        "INSERT OR REPLACE INTO schema_enums (parameter, value, name) VALUES (?1, ?2, ?3)",
+    )?;
+
+    for event in events_definition.values() {
+        insert_event.execute(params![event.id, event.name])?;
+        for (position, parameter) in event.parameters.iter().enumerate() {
+            let (number_of_bytes, length_reference) = match parameter.number_of_bytes {
+                -1 => (None, Some(parameter.related_name.as_str())),
+                n => (Some(n), None),
+            };
+            insert_parameter.execute(params![
+                event.id,
+                position,
+                parameter.name,
+                parameter.param_type,
+                number_of_bytes,
+                length_reference
+            ])?;
+            for (value, name) in &parameter.enumeration {
+                insert_enum.execute(params![parameter.name, value, name])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl EventWriter for SqliteWriter {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        self.insert(event).map_err(to_io_error)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.connection.execute_batch("COMMIT").map_err(to_io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_reader::event;
+
+    fn parameter(name: &str, param_type: &str, number_of_bytes: i64) -> Paramter {
+        Paramter {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            number_of_bytes,
+            enumeration: HashMap::new(),
+            related_name: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sqlite_writer() {
+        let mut events_definition = HashMap::new();
+        events_definition.insert(
+            5,
+            Event {
+                name: "INTERNAL_PROC_RRC_CONN_SETUP".to_string(),
+                id: 5,
+                elements: vec![],
+                parameters: vec![
+                    parameter("EVENT_PARAM_RAC_UE_REF", "UINT", 4),
+                    parameter("EVENT_PARAM_L3MESSAGE_CONTENTS", "BINARY", -1),
+                    parameter("EVENT_PARAM_RESULT", "ENUM", 1),
+                ],
+            },
+        );
+        let path = std::env::temp_dir().join(format!("ran_parser_{}.sqlite", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut writer = SqliteWriter::new(path, &events_definition, "A.bin.gz").unwrap();
+        writer
+            .write(&event(
+                "INTERNAL_PROC_RRC_CONN_SETUP",
+                1000,
+                &[
+                    ("EVENT_PARAM_RAC_UE_REF", "42"),
+                    ("EVENT_PARAM_L3MESSAGE_CONTENTS", "0aff"),
+                ],
+            ))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let connection = Connection::open(path).unwrap();
+        let (ueref, contents, file): (i64, Vec<u8>, String) = connection
+            .query_row(
+                "SELECT t.EVENT_PARAM_RAC_UE_REF, t.EVENT_PARAM_L3MESSAGE_CONTENTS, f.name
+                 FROM INTERNAL_PROC_RRC_CONN_SETUP t
+                 JOIN events e ON e.id = t.event_id
+                 JOIN files f ON f.id = e.file_id",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(ueref, 42);
+        assert_eq!(contents, vec![0x0a, 0xff]);
+        assert_eq!(file, "A.bin.gz");
+
+        let parameters: i64 = connection
+            .query_row("SELECT count(*) FROM schema_parameters", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(parameters, 3);
+        std::fs::remove_file(path).unwrap();
+    }
+}
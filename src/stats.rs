@@ -0,0 +1,121 @@
+use serde::Serialize;
+
+/// Summary statistics of a set of numeric values, with nearest-rank
+/// percentiles.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Statistics {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub stddev: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+/// Collects numeric values to summarise them.
+#[derive(Clone, Debug, Default)]
+pub struct Distribution {
+    values: Vec<f64>,
+}
+
+impl Distribution {
+    pub fn add(&mut self, value: f64) {
+        self.values.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    pub fn statistics(&self) -> Option<Statistics> {
+        counted_statistics(self.values.iter().map(|&value| (value, 1)).collect())
+    }
+}
+
+/// Statistics of values given with their number of occurrences, without
+/// expanding them into one value per occurrence.
+pub fn counted_statistics(mut counts: Vec<(f64, u64)>) -> Option<Statistics> {
+    counts.retain(|&(_, count)| count > 0);
+    counts.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let total: u64 = counts.iter().map(|&(_, count)| count).sum();
+    if total == 0 {
+        return None;
+    }
+    let n = total as f64;
+    let mean = counts
+        .iter()
+        .map(|&(value, count)| value * count as f64)
+        .sum::<f64>()
+        / n;
+    let variance = counts
+        .iter()
+        .map(|&(value, count)| (value - mean).powi(2) * count as f64)
+        .sum::<f64>()
+        / n;
+    let percentile = |percent: f64| {
+        let rank = ((percent / 100.0 * n).ceil() as u64).clamp(1, total);
+        let mut seen = 0;
+        for &(value, count) in &counts {
+            seen += count;
+            if seen >= rank {
+                return value;
+            }
+        }
+        counts[counts.len() - 1].0
+    };
+    Some(Statistics {
+        count: total,
+        min: counts[0].0,
+        max: counts[counts.len() - 1].0,
+        mean,
+        stddev: variance.sqrt(),
+        p10: percentile(10.0),
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics() {
+        let mut distribution = Distribution::default();
+        assert_eq!(distribution.statistics(), None);
+        for value in [4.0, 1.0, 3.0, 2.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0] {
+            distribution.add(value);
+        }
+        let statistics = distribution.statistics().unwrap();
+        assert_eq!((statistics.min, statistics.max), (1.0, 10.0));
+        assert_eq!(statistics.mean, 5.5);
+        assert_eq!(
+            (statistics.p10, statistics.p50, statistics.p90),
+            (1.0, 5.0, 9.0)
+        );
+        assert!((statistics.stddev - 2.8723).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_counted_statistics() {
+        assert_eq!(counted_statistics(vec![(1.0, 0)]), None);
+        let statistics = counted_statistics(vec![(40.0, 1), (10.0, 3), (25.0, 0)]).unwrap();
+        assert_eq!(statistics.count, 4);
+        assert_eq!((statistics.min, statistics.max), (10.0, 40.0));
+        assert_eq!(statistics.mean, 17.5);
+        assert_eq!(
+            (statistics.p10, statistics.p50, statistics.p90),
+            (10.0, 10.0, 40.0)
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use super::converter::is_numeric;
+use super::kpi::event_cells;
+use super::session::{build_sessions, GLOBAL_CELL_ID, IDENTIFIERS};
+use super::stats::{Distribution, Statistics};
+use super::trace_reader::TraceEvent;
+use super::units::convert;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+/// Number of events of one type in one cell and time bin.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct EventCount {
+    pub bin_start: u64,
+    pub cell: String,
+    pub event: String,
+    pub count: u64,
+}
+
+/// Distribution of one numeric parameter of an event type in one cell and
+/// time bin, in its physical unit when it has one.
+#[derive(Debug, Serialize)]
+pub struct ParameterSeries {
+    pub bin_start: u64,
+    pub cell: String,
+    pub event: String,
+    pub parameter: String,
+    pub statistics: Statistics,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeSeries {
+    pub bin_ms: u64,
+    pub event_counts: Vec<EventCount>,
+    pub parameters: Vec<ParameterSeries>,
+}
+
+type SeriesKey<'a> = (u64, &'a str, &'a str);
+
+/// Aggregates the events into time bins of `bin_ms` per cell: event counts
+/// per type, and the distribution of the numeric parameters declared as
+/// integers in `parameter_types`. UE identifiers, the cell id and the
+/// timestamp parameters are left out.
+pub fn time_series(
+    events: &[TraceEvent],
+    parameter_types: &HashMap<String, String>,
+    bin_ms: u64,
+) -> TimeSeries {
+    let bin_ms = bin_ms.max(1);
+    let cells = event_cells(events, &build_sessions(events));
+    let mut counts: BTreeMap<SeriesKey, u64> = BTreeMap::new();
+    let mut distributions: BTreeMap<(SeriesKey, &str), Distribution> = BTreeMap::new();
+
+    for (event, cell) in events.iter().zip(&cells) {
+        let key = (
+            event.timestamp - event.timestamp % bin_ms,
+            cell.as_str(),
+            event.name.as_str(),
+        );
+        *counts.entry(key).or_default() += 1;
+        for parameter in &event.parameters {
+            let name = parameter.name.as_str();
+            if !is_measure(name, parameter_types) {
+                continue;
+            }
+            let value = match convert(name, &parameter.value) {
+                Some((value, _)) => Some(value),
+                None => parameter.value.parse::<f64>().ok(),
+            };
+            if let Some(value) = value {
+                distributions.entry((key, name)).or_default().add(value);
+            }
+        }
+    }
+
+    TimeSeries {
+        bin_ms,
+        event_counts: counts
+            .into_iter()
+            .map(|((bin_start, cell, event), count)| EventCount {
+                bin_start,
+                cell: cell.to_string(),
+                event: event.to_string(),
+                count,
+            })
+            .collect(),
+        parameters: distributions
+            .into_iter()
+            .filter_map(|(((bin_start, cell, event), parameter), distribution)| {
+                Some(ParameterSeries {
+                    bin_start,
+                    cell: cell.to_string(),
+                    event: event.to_string(),
+                    parameter: parameter.to_string(),
+                    statistics: distribution.statistics()?,
+                })
+            })
+            .collect(),
+    }
+}
+
+fn is_measure(name: &str, parameter_types: &HashMap<String, String>) -> bool {
+    parameter_types
+        .get(name)
+        .is_some_and(|parameter_type| is_numeric(parameter_type))
+        && !IDENTIFIERS.contains(&name)
+        && name != GLOBAL_CELL_ID
+        && !name.contains("TIMESTAMP")
+}
+
+/// Writes the series as one CSV table. Event count rows leave the
+/// parameter and the statistics empty.
+pub fn write_series_csv<W: Write>(output: W, series: &TimeSeries) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    writer.write_record([
+        "bin_start",
+        "cell",
+        "event",
+        "parameter",
+        "count",
+        "min",
+        "mean",
+        "max",
+        "p10",
+        "p50",
+        "p90",
+    ])?;
+    for count in &series.event_counts {
+        writer.write_record([
+            count.bin_start.to_string().as_str(),
+            count.cell.as_str(),
+            count.event.as_str(),
+            "",
+            count.count.to_string().as_str(),
+            "",
+            "",
+            "",
+            "",
+            "",
+            "",
+        ])?;
+    }
+    for parameter in &series.parameters {
+        let statistics = &parameter.statistics;
+        writer.write_record([
+            parameter.bin_start.to_string(),
+            parameter.cell.to_string(),
+            parameter.event.to_string(),
+            parameter.parameter.to_string(),
+            statistics.count.to_string(),
+            statistics.min.to_string(),
+            statistics.mean.to_string(),
+            statistics.max.to_string(),
+            statistics.p10.to_string(),
+            statistics.p50.to_string(),
+            statistics.p90.to_string(),
+        ])?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::RAC_UE_REF;
+    use crate::trace_reader::event;
+
+    fn traffic_report(timestamp: u64, ueref: &str, volume: &str) -> TraceEvent {
+        event(
+            "INTERNAL_PER_UE_TRAFFIC_REP",
+            timestamp,
+            &[
+                (RAC_UE_REF, ueref),
+                (GLOBAL_CELL_ID, "11"),
+                ("EVENT_PARAM_DL_VOLUME", volume),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_time_series() {
+        let events = vec![
+            traffic_report(500, "1", "10"),
+            traffic_report(900, "2", "30"),
+            traffic_report(1500, "1", "20"),
+        ];
+        let mut parameter_types = HashMap::new();
+        for name in [RAC_UE_REF, GLOBAL_CELL_ID, "EVENT_PARAM_DL_VOLUME"] {
+            parameter_types.insert(name.to_string(), "UINT".to_string());
+        }
+        let series = time_series(&events, &parameter_types, 1000);
+
+        assert_eq!(
+            series
+                .event_counts
+                .iter()
+                .map(|count| (count.bin_start, count.count))
+                .collect::<Vec<_>>(),
+            vec![(0, 2), (1000, 1)]
+        );
+        assert_eq!(series.parameters.len(), 2);
+        let first = &series.parameters[0];
+        assert_eq!(first.parameter, "EVENT_PARAM_DL_VOLUME");
+        assert_eq!(
+            (
+                first.statistics.min,
+                first.statistics.mean,
+                first.statistics.max
+            ),
+            (10.0, 20.0, 30.0)
+        );
+
+        let mut csv = Vec::new();
+        write_series_csv(&mut csv, &series).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("0,11,INTERNAL_PER_UE_TRAFFIC_REP,,2,,,,,,")
+        );
+    }
+}
@@ -1,15 +1,29 @@
 use flate2::read::GzDecoder;
+use log::{debug, info, warn};
 use std::collections::HashMap;
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 use std::io::BufReader;
 
 use super::config::Config;
 use super::parser::RowParser;
-use super::xml_parser::{parse_xml, Event};
+use super::xml_parser::{parse_xml, Diagnostic, Event};
 
 const RECORD_LENGTH: u16 = 2;
 const RECORD_TYPE: u16 = 2;
+const EVENT_RECORD: u16 = 4;
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+/// Why a record was not decoded, counted per reason and only formatted when
+/// the statistics are logged.
+#[derive(PartialEq, Eq, Hash)]
+enum SkipReason {
+    RecordType(u16),
+    Truncated,
+    NotInSchema(u16),
+    NotMatchingFilter,
+}
 
 #[derive(Debug)]
 pub struct TraceParameter {
@@ -29,8 +43,25 @@ pub struct TraceEvent {
     pub timestamp: u64,
 }
 
+/// Builds an event from `(name, value)` parameter pairs.
+#[cfg(test)]
+pub fn event(name: &str, timestamp: u64, parameters: &[(&str, &str)]) -> TraceEvent {
+    TraceEvent {
+        name: name.to_string(),
+        parameters: parameters
+            .iter()
+            .map(|(name, value)| TraceParameter {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+        timestamp,
+    }
+}
+
 fn get_file_reader(filename: &str) -> GzDecoder<BufReader<File>> {
-    let file = File::open(filename).expect(&format!("not able to parse file {}", filename));
+    let file =
+        File::open(filename).unwrap_or_else(|_| panic!("not able to parse file {}", filename));
     let reader = BufReader::new(file);
     let gz_reader: GzDecoder<BufReader<File>> = GzDecoder::new(reader);
     gz_reader
@@ -41,36 +72,106 @@ pub struct TraceReader {
     gz_reader: GzDecoder<BufReader<File>>,
     pub decoded_trace_events: Vec<TraceEvent>,
     filter: String,
+    filename: String,
+    records: u64,
+    decoded: u64,
+    skipped: HashMap<SkipReason, u64>,
 }
 impl TraceReader {
-    pub fn new(config: &Config) -> Self {
-        Self {
-            events_definition: parse_xml(&config.xml),
-            gz_reader: get_file_reader(&config.filename),
+    pub fn new(config: &Config) -> Result<Self, Vec<Diagnostic>> {
+        Self::open(config, &config.filename)
+    }
+
+    /// Reader for another trace file than the configured one, with the same
+    /// schema and filter.
+    pub fn open(config: &Config, filename: &str) -> Result<Self, Vec<Diagnostic>> {
+        Ok(Self {
+            events_definition: parse_xml(&config.xml)?,
+            gz_reader: get_file_reader(filename),
             decoded_trace_events: Vec::new(),
             filter: config.filter.to_string(),
-        }
+            filename: filename.to_string(),
+            records: 0,
+            decoded: 0,
+            skipped: HashMap::new(),
+        })
+    }
+    pub fn events_definition(&self) -> &HashMap<u16, Event> {
+        &self.events_definition
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
     }
+
     pub fn read_trace(&mut self) {
+        let mut events = Vec::new();
+        let _ = self.read_trace_with(|event| {
+            events.push(event);
+            Ok(())
+        });
+        self.decoded_trace_events = events;
+    }
+
+    /// Decodes the trace one event at a time without keeping the events in
+    /// memory, stopping at the first error returned by `handle`.
+    // `u64::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn read_trace_with<F>(&mut self, mut handle: F) -> io::Result<()>
+    where
+        F: FnMut(TraceEvent) -> io::Result<()>,
+    {
+        info!("reading {}", self.filename);
         loop {
             let mut event_length_in_bytes = [0; RECORD_LENGTH as usize];
 
             match self.gz_reader.read_exact(&mut event_length_in_bytes) {
                 Ok(_) => {}
                 Err(_) => {
-                    return;
+                    self.log_statistics();
+                    return Ok(());
                 }
             };
 
             let event_type = self.get_event_type();
             let raw_event = self.get_raw_event(event_length_in_bytes);
 
-            if event_type == 4 {
-                self.decode_raw_event(raw_event)
+            self.records += 1;
+            if self.records % PROGRESS_INTERVAL == 0 {
+                debug!("{}: {} records read", self.filename, self.records);
+            }
+
+            if event_type != EVENT_RECORD {
+                self.skip(SkipReason::RecordType(event_type));
+            } else if let Some(event) = self.decode_raw_event(raw_event) {
+                self.decoded += 1;
+                handle(event)?;
             }
         }
     }
 
+    fn skip(&mut self, reason: SkipReason) {
+        *self.skipped.entry(reason).or_insert(0) += 1;
+    }
+
+    fn log_statistics(&self) {
+        info!(
+            "{}: {} records read, {} events decoded",
+            self.filename, self.records, self.decoded
+        );
+        for (reason, count) in &self.skipped {
+            let reason = match reason {
+                SkipReason::RecordType(record_type) => format!("record type {}", record_type),
+                SkipReason::Truncated => "truncated event record".to_string(),
+                SkipReason::NotInSchema(id) => format!("event id {} not in schema", id),
+                SkipReason::NotMatchingFilter => {
+                    format!("event not matching filter {}", self.filter)
+                }
+            };
+            info!("{}: {} records skipped, {}", self.filename, count, reason);
+        }
+    }
+
     fn get_raw_event(&mut self, event_length_in_bytes: [u8; 2]) -> Vec<u8> {
         let envet_length: u16 =
             u16::from_be_bytes(event_length_in_bytes) - RECORD_LENGTH - RECORD_TYPE;
@@ -85,12 +186,24 @@ impl TraceReader {
         u16::from_be_bytes(event_type)
     }
 
-    fn decode_raw_event(&mut self, raw_event: Vec<u8>) {
+    fn decode_raw_event(&mut self, raw_event: Vec<u8>) -> Option<TraceEvent> {
+        if raw_event.len() < 3 {
+            warn!("{}: truncated event record", self.filename);
+            self.skip(SkipReason::Truncated);
+            return None;
+        }
+        let id = u16::from_be_bytes([raw_event[1], raw_event[2]]);
+        if !self.events_definition.contains_key(&id) {
+            debug!("{}: event id {} is not in the schema", self.filename, id);
+            self.skip(SkipReason::NotInSchema(id));
+            return None;
+        }
+
         let mut parser = RowParser::new(raw_event, &self.filter);
         let event = parser.parse(&self.events_definition);
-        match event {
-            Some(event) => self.decoded_trace_events.push(event),
-            None => {}
+        if event.is_none() {
+            self.skip(SkipReason::NotMatchingFilter);
         }
+        event
     }
 }
@@ -0,0 +1,176 @@
+use super::kpi::event_cells;
+use super::session::build_sessions;
+use super::stats::{Distribution, Statistics};
+use super::trace_reader::TraceEvent;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Metres of one-way distance per timing advance unit. The eNB reports
+/// the timing advance in basic time units, Ts = 1 / 30.72 MHz.
+pub(crate) const TA_METRES: f64 = 299_792_458.0 / 30_720_000.0 / 2.0;
+
+/// Physical unit of a reported value: `value * scale + offset`.
+pub struct Conversion {
+    pub pattern: &'static str,
+    pub unit: &'static str,
+    pub scale: f64,
+    pub offset: f64,
+}
+
+/// Conversions applied to the parameters whose name contains the pattern
+/// as whole `_` separated words, following the reporting ranges of 3GPP TS
+/// 36.133.
+const CONVERSIONS: [Conversion; 6] = [
+    Conversion {
+        pattern: "RSRP",
+        unit: "dBm",
+        scale: 1.0,
+        offset: -140.0,
+    },
+    Conversion {
+        pattern: "RSRQ",
+        unit: "dB",
+        scale: 0.5,
+        offset: -20.0,
+    },
+    Conversion {
+        pattern: "SINR",
+        unit: "dB",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Conversion {
+        pattern: "CQI",
+        unit: "",
+        scale: 1.0,
+        offset: 0.0,
+    },
+    Conversion {
+        pattern: "TA",
+        unit: "m",
+        scale: TA_METRES,
+        offset: 0.0,
+    },
+    Conversion {
+        pattern: "POWER_HEADROOM",
+        unit: "dB",
+        scale: 1.0,
+        offset: -23.0,
+    },
+];
+
+pub fn conversion(name: &str) -> Option<&'static Conversion> {
+    let name = format!("_{}_", name);
+    CONVERSIONS
+        .iter()
+        .find(|conversion| name.contains(&format!("_{}_", conversion.pattern)))
+}
+
+/// The value of a parameter in its physical unit, with the unit, if the
+/// parameter has a conversion and a numeric value.
+pub fn convert(name: &str, value: &str) -> Option<(f64, &'static str)> {
+    let conversion = conversion(name)?;
+    let raw: f64 = value.parse().ok()?;
+    Some((raw * conversion.scale + conversion.offset, conversion.unit))
+}
+
+/// Name of the column holding a parameter in its physical unit, like
+/// `EVENT_PARAM_SERVING_RSRP_dBm`, next to the one with the reported value.
+/// Parameters without a unit get none.
+pub fn converted_column(name: &str) -> Option<String> {
+    match conversion(name)?.unit {
+        "" => None,
+        unit => Some(format!("{}_{}", name, unit)),
+    }
+}
+
+/// The radio measurements of an event in their physical unit, by the name
+/// of their converted column.
+pub fn converted_values(event: &TraceEvent) -> HashMap<String, String> {
+    event
+        .parameters
+        .iter()
+        .filter_map(|parameter| {
+            let column = converted_column(&parameter.name)?;
+            let (value, _) = convert(&parameter.name, &parameter.value)?;
+            Some((column, value.to_string()))
+        })
+        .collect()
+}
+
+/// A converted value for display, like `-95 dBm` or `312.3 m`.
+pub fn format_converted(value: f64, unit: &str) -> String {
+    let value = (value * 10.0).round() / 10.0;
+    match unit {
+        "" => value.to_string(),
+        unit => format!("{} {}", value, unit),
+    }
+}
+
+/// Distribution of one measurement parameter in one cell.
+#[derive(Debug, Serialize)]
+pub struct CellMeasurement {
+    pub cell: String,
+    pub parameter: String,
+    pub unit: String,
+    pub statistics: Statistics,
+}
+
+/// Distributions of the converted radio measurements per cell.
+pub fn measurement_statistics(events: &[TraceEvent]) -> Vec<CellMeasurement> {
+    let cells = event_cells(events, &build_sessions(events));
+    let mut distributions: BTreeMap<(&str, &str), (Distribution, &str)> = BTreeMap::new();
+    for (event, cell) in events.iter().zip(&cells) {
+        for parameter in &event.parameters {
+            if let Some((value, unit)) = convert(&parameter.name, &parameter.value) {
+                distributions
+                    .entry((cell, &parameter.name))
+                    .or_insert_with(|| (Distribution::default(), unit))
+                    .0
+                    .add(value);
+            }
+        }
+    }
+    distributions
+        .into_iter()
+        .filter_map(|((cell, parameter), (distribution, unit))| {
+            Some(CellMeasurement {
+                cell: cell.to_string(),
+                parameter: parameter.to_string(),
+                unit: unit.to_string(),
+                statistics: distribution.statistics()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(
+            convert("EVENT_PARAM_SERVING_RSRP", "45"),
+            Some((-95.0, "dBm"))
+        );
+        assert_eq!(
+            convert("EVENT_PARAM_SERVING_RSRQ", "30"),
+            Some((-5.0, "dB"))
+        );
+        assert_eq!(convert("EVENT_PARAM_TAC", "30"), None);
+        assert_eq!(convert("EVENT_PARAM_SERVING_RSRP", "na"), None);
+        let (metres, unit) = convert("EVENT_ARRAY_TA", "64").unwrap();
+        assert_eq!(format_converted(metres, unit), "312.3 m");
+    }
+
+    #[test]
+    fn test_converted_column() {
+        assert_eq!(
+            converted_column("EVENT_PARAM_SERVING_RSRP").as_deref(),
+            Some("EVENT_PARAM_SERVING_RSRP_dBm")
+        );
+        assert_eq!(converted_column("EVENT_PARAM_CQI"), None);
+        assert_eq!(converted_column("EVENT_PARAM_TAC"), None);
+    }
+}
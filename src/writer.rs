@@ -0,0 +1,306 @@
+use super::config::CsvLayout;
+use super::converter::is_numeric;
+use super::l3::decode_event;
+use super::trace_reader::TraceEvent;
+use super::units::{convert, converted_column};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+pub trait EventWriter {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+/// Opens `path` for writing, or stdout when no path is given.
+pub fn create_output(path: &Option<String>) -> io::Result<Box<dyn Write>> {
+    match path {
+        Some(path) => Ok(Box::new(BufWriter::new(File::create(path)?))),
+        None => Ok(Box::new(BufWriter::new(io::stdout()))),
+    }
+}
+
+pub fn create_csv_writer(
+    layout: &CsvLayout,
+    path: &Option<String>,
+) -> io::Result<Box<dyn EventWriter>> {
+    let writer: Box<dyn EventWriter> = match layout {
+        CsvLayout::Long => Box::new(CsvLongWriter::new(create_output(path)?)?),
+        CsvLayout::Wide => {
+            let directory = path
+                .as_deref()
+                .expect("csv-wide requires an output directory");
+            Box::new(CsvWideWriter::new(directory)?)
+        }
+    };
+    Ok(writer)
+}
+
+/// Long/narrow layout: one `event,timestamp,param,value` row per parameter,
+/// plus a `PARAM [unit]` row for radio measurements in their physical unit,
+/// followed by one row per field of the decoded L3 message.
+pub struct CsvLongWriter<W: Write> {
+    writer: csv::Writer<W>,
+}
+
+impl<W: Write> CsvLongWriter<W> {
+    pub fn new(output: W) -> io::Result<Self> {
+        let mut writer = csv::Writer::from_writer(output);
+        writer.write_record(["event", "timestamp", "param", "value"])?;
+        Ok(Self { writer })
+    }
+}
+
+impl<W: Write> EventWriter for CsvLongWriter<W> {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let timestamp = event.timestamp.to_string();
+        for parameter in &event.parameters {
+            self.writer.write_record([
+                event.name.as_str(),
+                timestamp.as_str(),
+                parameter.name.as_str(),
+                parameter.value.as_str(),
+            ])?;
+            if let Some((value, unit)) = convert(&parameter.name, &parameter.value) {
+                if !unit.is_empty() {
+                    self.writer.write_record([
+                        event.name.as_str(),
+                        timestamp.as_str(),
+                        format!("{} [{}]", parameter.name, unit).as_str(),
+                        value.to_string().as_str(),
+                    ])?;
+                }
+            }
+        }
+        if let Some(message) = decode_event(event) {
+            for (path, value) in message.flatten() {
+                self.writer.write_record([
+                    event.name.as_str(),
+                    timestamp.as_str(),
+                    path.as_str(),
+                    value.as_str(),
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Wide layout: one `<EVENT_NAME>.csv` file per event type in `directory`,
+/// with the event parameters as columns, each radio measurement followed by
+/// a `<PARAM>_<unit>` column with its value in the physical unit.
+pub struct CsvWideWriter {
+    directory: PathBuf,
+    writers: HashMap<String, csv::Writer<File>>,
+}
+
+impl CsvWideWriter {
+    pub fn new(directory: &str) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            directory: PathBuf::from(directory),
+            writers: HashMap::new(),
+        })
+    }
+
+    fn writer_for(&mut self, event: &TraceEvent) -> io::Result<&mut csv::Writer<File>> {
+        if !self.writers.contains_key(&event.name) {
+            let path = self.directory.join(format!("{}.csv", event.name));
+            let mut writer = csv::Writer::from_path(path)?;
+            let mut header = vec!["timestamp".to_string()];
+            for parameter in &event.parameters {
+                header.push(parameter.name.to_string());
+                header.extend(converted_column(&parameter.name));
+            }
+            writer.write_record(&header)?;
+            self.writers.insert(event.name.to_string(), writer);
+        }
+        Ok(self.writers.get_mut(&event.name).unwrap())
+    }
+}
+
+impl EventWriter for CsvWideWriter {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let writer = self.writer_for(event)?;
+        let mut row = vec![event.timestamp.to_string()];
+        for parameter in &event.parameters {
+            row.push(parameter.value.to_string());
+            if converted_column(&parameter.name).is_some() {
+                row.push(match convert(&parameter.name, &parameter.value) {
+                    Some((value, _)) => value.to_string(),
+                    None => String::new(),
+                });
+            }
+        }
+        writer.write_record(&row)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        for writer in self.writers.values_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// One JSON object per line, with integer parameters written as numbers,
+/// radio measurements in their physical unit under `converted` and the
+/// decoded L3 message, if any, under `l3`.
+pub struct JsonLinesWriter<W: Write> {
+    output: W,
+    file: String,
+    parameter_types: HashMap<String, String>,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(output: W, file: &str, parameter_types: HashMap<String, String>) -> Self {
+        Self {
+            output,
+            file: file.to_string(),
+            parameter_types,
+        }
+    }
+
+    fn typed_value(&self, name: &str, value: &str) -> Value {
+        let numeric = match self.parameter_types.get(name) {
+            Some(param_type) => is_numeric(param_type),
+            None => false,
+        };
+        match value.parse::<u64>() {
+            Ok(number) if numeric => Value::from(number),
+            _ => Value::from(value),
+        }
+    }
+}
+
+impl<W: Write> EventWriter for JsonLinesWriter<W> {
+    fn write(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let mut params = Map::new();
+        let mut converted = Map::new();
+        for parameter in &event.parameters {
+            params.insert(
+                parameter.name.to_string(),
+                self.typed_value(&parameter.name, &parameter.value),
+            );
+            if let Some((value, unit)) = convert(&parameter.name, &parameter.value) {
+                converted.insert(
+                    parameter.name.to_string(),
+                    json!({"value": value, "unit": unit}),
+                );
+            }
+        }
+        let mut line = json!({
+            "event": event.name,
+            "timestamp": event.timestamp,
+            "file": self.file,
+            "params": params,
+        });
+        if !converted.is_empty() {
+            line["converted"] = Value::Object(converted);
+        }
+        if let Some(message) = decode_event(event) {
+            line["l3"] = serde_json::to_value(message)?;
+        }
+        serde_json::to_writer(&mut self.output, &line)?;
+        self.output.write_all(b"\n")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace_reader::event;
+
+    fn connection_setup() -> TraceEvent {
+        event(
+            "INTERNAL_PROC_RRC_CONN_SETUP",
+            1000,
+            &[
+                ("EVENT_PARAM_RAC_UE_REF", "42"),
+                ("EVENT_PARAM_CAUSE", "a,b"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_csv_long_writer() {
+        let mut output: Vec<u8> = Vec::new();
+        {
+            let mut writer = CsvLongWriter::new(&mut output).unwrap();
+            writer.write(&connection_setup()).unwrap();
+            writer.finish().unwrap();
+        }
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "event,timestamp,param,value\n\
+             INTERNAL_PROC_RRC_CONN_SETUP,1000,EVENT_PARAM_RAC_UE_REF,42\n\
+             INTERNAL_PROC_RRC_CONN_SETUP,1000,EVENT_PARAM_CAUSE,\"a,b\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json_lines_writer() {
+        let mut parameter_types = HashMap::new();
+        parameter_types.insert("EVENT_PARAM_RAC_UE_REF".to_string(), "UINT".to_string());
+        parameter_types.insert("EVENT_PARAM_CAUSE".to_string(), "ENUM".to_string());
+
+        let mut output: Vec<u8> = Vec::new();
+        {
+            let mut writer = JsonLinesWriter::new(&mut output, "A.bin.gz", parameter_types);
+            writer.write(&connection_setup()).unwrap();
+            writer.write(&connection_setup()).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let line = r#"{"event":"INTERNAL_PROC_RRC_CONN_SETUP","timestamp":1000,"file":"A.bin.gz","params":{"EVENT_PARAM_RAC_UE_REF":42,"EVENT_PARAM_CAUSE":"a,b"}}"#;
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("{}\n{}\n", line, line)
+        );
+    }
+
+    #[test]
+    fn test_csv_wide_writer() {
+        let directory = std::env::temp_dir().join(format!("ran_parser_csv_{}", std::process::id()));
+        let mut writer = CsvWideWriter::new(directory.to_str().unwrap()).unwrap();
+        writer.write(&connection_setup()).unwrap();
+        writer.write(&connection_setup()).unwrap();
+        writer
+            .write(&event(
+                "INTERNAL_PER_RADIO_UE_MEASUREMENT",
+                2000,
+                &[("EVENT_PARAM_SERVING_RSRP", "45")],
+            ))
+            .unwrap();
+        writer.finish().unwrap();
+
+        let content =
+            fs::read_to_string(directory.join("INTERNAL_PROC_RRC_CONN_SETUP.csv")).unwrap();
+        assert_eq!(
+            content,
+            "timestamp,EVENT_PARAM_RAC_UE_REF,EVENT_PARAM_CAUSE\n\
+             1000,42,\"a,b\"\n\
+             1000,42,\"a,b\"\n"
+        );
+        let content =
+            fs::read_to_string(directory.join("INTERNAL_PER_RADIO_UE_MEASUREMENT.csv")).unwrap();
+        assert_eq!(
+            content,
+            "timestamp,EVENT_PARAM_SERVING_RSRP,EVENT_PARAM_SERVING_RSRP_dBm\n\
+             2000,45,-95\n"
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+use super::l3::L3Field;
+use super::per::PerReader;
+use super::s1ap::{
+    cause, decode_e_rab_item, decode_pdu, e_rab_list, ecgi, undecoded_ie, CauseGroup, ERabItem,
+    PROTOCOL_CAUSES, TRANSPORT_CAUSES,
+};
+
+/// Messages of each X2AP procedure, indexed by procedure code: initiating,
+/// successful and unsuccessful outcome.
+const PROCEDURES: [[&str; 3]; 16] = [
+    [
+        "HandoverRequest",
+        "HandoverRequestAcknowledge",
+        "HandoverPreparationFailure",
+    ],
+    ["HandoverCancel", "", ""],
+    ["LoadInformation", "", ""],
+    ["ErrorIndication", "", ""],
+    ["SNStatusTransfer", "", ""],
+    ["UEContextRelease", "", ""],
+    ["X2SetupRequest", "X2SetupResponse", "X2SetupFailure"],
+    ["ResetRequest", "ResetResponse", ""],
+    [
+        "ENBConfigurationUpdate",
+        "ENBConfigurationUpdateAcknowledge",
+        "ENBConfigurationUpdateFailure",
+    ],
+    [
+        "ResourceStatusRequest",
+        "ResourceStatusResponse",
+        "ResourceStatusFailure",
+    ],
+    ["ResourceStatusUpdate", "", ""],
+    ["PrivateMessage", "", ""],
+    [
+        "MobilityChangeRequest",
+        "MobilityChangeAcknowledge",
+        "MobilityChangeFailure",
+    ],
+    ["RLFIndication", "", ""],
+    ["HandoverReport", "", ""],
+    [
+        "CellActivationRequest",
+        "CellActivationResponse",
+        "CellActivationFailure",
+    ],
+];
+
+const RADIO_NETWORK_CAUSES: [&str; 22] = [
+    "handover-desirable-for-radio-reasons",
+    "time-critical-handover",
+    "resource-optimisation-handover",
+    "reduce-load-in-serving-cell",
+    "partial-handover",
+    "unknown-new-eNB-UE-X2AP-ID",
+    "unknown-old-eNB-UE-X2AP-ID",
+    "unknown-pair-of-UE-X2AP-ID",
+    "ho-target-not-allowed",
+    "tx2relocoverall-expiry",
+    "trelocprep-expiry",
+    "cell-not-available",
+    "no-radio-resources-available-in-target-cell",
+    "invalid-MME-GroupID",
+    "unknown-MME-Code",
+    "encryption-and-or-integrity-protection-algorithms-not-supported",
+    "reportCharacteristicsEmpty",
+    "noReportPeriodicity",
+    "existingMeasurementID",
+    "unknown-eNB-Measurement-ID",
+    "measurement-temporarily-not-available",
+    "unspecified",
+];
+const RADIO_NETWORK_CAUSE_EXTENSIONS: [&str; 7] = [
+    "load-balancing",
+    "handover-optimisation",
+    "value-out-of-allowed-range",
+    "multiple-E-RAB-ID-instances",
+    "switch-off-ongoing",
+    "not-supported-QCI-value",
+    "measurement-not-supported-for-the-object",
+];
+const MISC_CAUSES: [&str; 5] = [
+    "control-processing-overload",
+    "hardware-failure",
+    "om-intervention",
+    "not-enough-user-plane-processing-resources",
+    "unspecified",
+];
+
+const CAUSE_GROUPS: [CauseGroup; 4] = [
+    (
+        "radioNetwork",
+        &RADIO_NETWORK_CAUSES,
+        &RADIO_NETWORK_CAUSE_EXTENSIONS,
+    ),
+    ("transport", &TRANSPORT_CAUSES, &[]),
+    ("protocol", &PROTOCOL_CAUSES, &[]),
+    ("misc", &MISC_CAUSES, &[]),
+];
+
+/// Names of the IEs that are shown undecoded.
+const OTHER_IES: [(u16, &str); 8] = [
+    (12, "TargeteNBtoSource-eNBTransparentContainer"),
+    (13, "TraceActivation"),
+    (14, "UE-ContextInformation"),
+    (15, "UE-HistoryInformation"),
+    (17, "CriticalityDiagnostics"),
+    (18, "E-RABs-SubjectToStatusTransfer-List"),
+    (21, "GlobalENB-ID"),
+    (23, "GUMMEI-ID"),
+];
+
+/// Decodes an X2AP PDU (3GPP TS 36.423).
+pub fn decode(contents: &[u8]) -> L3Field {
+    decode_pdu("X2AP-PDU", contents, message_name, decode_ie)
+}
+
+fn message_name(procedure: usize, pdu_type: usize) -> Option<&'static str> {
+    PROCEDURES
+        .get(procedure)
+        .map(|messages| messages[pdu_type])
+        .filter(|name| !name.is_empty())
+}
+
+fn decode_ie(id: u16, value: &[u8]) -> L3Field {
+    let mut reader = PerReader::aligned(value);
+    let field = match id {
+        1 => e_rab_list(&mut reader, "E-RABs-Admitted-List", e_rab_item),
+        3 => e_rab_list(&mut reader, "E-RABs-NotAdmitted-List", e_rab_item),
+        5 => cause(&mut reader, &CAUSE_GROUPS),
+        9 => ue_x2ap_id(&mut reader, "New-eNB-UE-X2AP-ID"),
+        10 => ue_x2ap_id(&mut reader, "Old-eNB-UE-X2AP-ID"),
+        11 => ecgi(&mut reader, "TargetCell-ID"),
+        _ => None,
+    };
+    field.unwrap_or_else(|| undecoded_ie(id, value, &OTHER_IES))
+}
+
+fn ue_x2ap_id(reader: &mut PerReader, name: &str) -> Option<L3Field> {
+    Some(L3Field::leaf(name, reader.constrained(0, 4095)?))
+}
+
+fn e_rab_item(id: u16, value: &[u8]) -> L3Field {
+    match id {
+        0 => decode_e_rab_item(
+            value,
+            "E-RABs-Admitted-Item",
+            3,
+            ERabItem::Id,
+            &CAUSE_GROUPS,
+        ),
+        2 => decode_e_rab_item(value, "E-RAB-Item", 1, ERabItem::Cause, &CAUSE_GROUPS),
+        _ => undecoded_ie(id, value, &[]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ue_context_release() {
+        // Old eNB UE X2AP ID 7, new eNB UE X2AP ID 300
+        let message = decode(&[
+            0x00, 0x05, 0x40, 0x0f, 0x00, 0x00, 0x02, 0x00, 0x0a, 0x40, 0x02, 0x00, 0x07, 0x00,
+            0x09, 0x40, 0x02, 0x01, 0x2c,
+        ]);
+        let release = message.find("UEContextRelease").unwrap();
+        assert_eq!(
+            release.find("Old-eNB-UE-X2AP-ID").unwrap().value.as_deref(),
+            Some("7")
+        );
+        assert_eq!(
+            release.find("New-eNB-UE-X2AP-ID").unwrap().value.as_deref(),
+            Some("300")
+        );
+    }
+}
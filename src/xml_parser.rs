@@ -4,9 +4,13 @@ use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
 
+use log::info;
+use xml::attribute::OwnedAttribute;
+use xml::common::{Position, TextPosition};
 use xml::name::OwnedName;
 use xml::reader::{EventReader, XmlEvent};
 
+#[derive(Clone)]
 pub struct Event {
     pub name: String,
     pub id: u16,
@@ -65,48 +69,127 @@ impl fmt::Display for Paramter {
     }
 }
 
-pub fn parse_xml(filename: &str) -> HashMap<u16, Event> {
-    let file = File::open(filename).expect(&format!("not able to parse file {}", filename));
-    let file = BufReader::new(file);
-    let mut parser = EventReader::new(file);
+/// A problem found in the schema, positioned at the element it was found in.
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: u64,
+    pub column: u64,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(position: TextPosition, message: String) -> Diagnostic {
+        Diagnostic {
+            line: position.row + 1,
+            column: position.column + 1,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+struct EventPositions {
+    event: TextPosition,
+    params: Vec<TextPosition>,
+}
+
+pub fn parse_xml(filename: &str) -> Result<HashMap<u16, Event>, Vec<Diagnostic>> {
+    let events = read_schema(open_schema(filename)?)?;
+    info!(
+        "loaded {} events with {} parameters from {}",
+        events.len(),
+        events
+            .values()
+            .map(|event| event.parameters.len())
+            .sum::<usize>(),
+        filename
+    );
+    Ok(events)
+}
+
+/// Returns every problem in the schema, or an empty list if it is valid.
+pub fn validate_xml(filename: &str) -> Vec<Diagnostic> {
+    match open_schema(filename).and_then(read_schema) {
+        Ok(_) => Vec::new(),
+        Err(diagnostics) => diagnostics,
+    }
+}
+
+fn open_schema(filename: &str) -> Result<BufReader<File>, Vec<Diagnostic>> {
+    let file = File::open(filename).map_err(|err| {
+        vec![Diagnostic {
+            line: 0,
+            column: 0,
+            message: format!("not able to open file {}: {}", filename, err),
+        }]
+    })?;
+    Ok(BufReader::new(file))
+}
+
+fn read_schema<R: Read>(source: R) -> Result<HashMap<u16, Event>, Vec<Diagnostic>> {
+    let mut parser = EventReader::new(source);
 
     let mut events: HashMap<u16, Event> = HashMap::new();
+    let mut positions: HashMap<u16, EventPositions> = HashMap::new();
     let mut paramters: HashMap<String, Paramter> = HashMap::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
 
     loop {
-        let event = &parser.next().unwrap();
+        let event = parser.next();
         match event {
-            XmlEvent::StartElement {
+            Ok(XmlEvent::StartElement {
                 name: OwnedName { local_name, .. },
                 ..
-            } if local_name.as_str() == "event" => {
-                parse_event(&mut parser, &mut events);
+            }) if local_name.as_str() == "event" => {
+                parse_event(&mut parser, &mut events, &mut positions, &mut diagnostics);
             }
-            XmlEvent::StartElement {
+            Ok(XmlEvent::StartElement {
                 name: OwnedName { local_name, .. },
                 ..
-            } if local_name.as_str() == "parametertype" => {
-                parse_parameter(&mut parser, &mut paramters);
+            }) if local_name.as_str() == "parametertype" => {
+                parse_parameter(&mut parser, &mut paramters, &mut diagnostics);
+            }
+            Ok(XmlEvent::EndDocument) => {
+                fill_events_with_paramters(&mut events, &positions, paramters, &mut diagnostics);
+                break;
             }
-            XmlEvent::EndDocument => {
-                fill_events_with_paramters(&mut events, paramters);
-                println!("{}", events.get(&1064).unwrap());
-                return events;
+            Err(err) => {
+                diagnostics.push(Diagnostic::new(err.position(), err.msg().to_string()));
+                break;
             }
             _ => {}
         }
     }
+
+    if diagnostics.is_empty() {
+        Ok(events)
+    } else {
+        diagnostics.sort_by_key(|d| (d.line, d.column));
+        Err(diagnostics)
+    }
 }
 
-fn parse_event<R: Read>(parser: &mut EventReader<R>, events: &mut HashMap<u16, Event>) {
+fn parse_event<R: Read>(
+    parser: &mut EventReader<R>,
+    events: &mut HashMap<u16, Event>,
+    positions: &mut HashMap<u16, EventPositions>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let start = parser.position();
     let mut data = String::new();
 
     let mut name = String::new();
-    let mut id = 0;
+    let mut id: Option<u16> = None;
     let mut elements: Vec<String> = Vec::new();
+    let mut params: Vec<TextPosition> = Vec::new();
 
     loop {
-        let event = &parser.next();
+        let event = parser.next();
 
         match event {
             Ok(XmlEvent::Characters(d)) => {
@@ -119,8 +202,18 @@ fn parse_event<R: Read>(parser: &mut EventReader<R>, events: &mut HashMap<u16, E
             }
             Ok(XmlEvent::EndElement {
                 name: OwnedName { local_name, .. },
-            }) if local_name.as_str() == "id" => {
-                id = data.parse().unwrap();
+            }) if local_name.as_str() == "id" => match data.trim().parse() {
+                Ok(value) => id = Some(value),
+                Err(_) => diagnostics.push(Diagnostic::new(
+                    parser.position(),
+                    format!("event {} has invalid id '{}'", name, data),
+                )),
+            },
+            Ok(XmlEvent::StartElement {
+                name: OwnedName { local_name, .. },
+                ..
+            }) if local_name.as_str() == "param" => {
+                params.push(parser.position());
             }
             Ok(XmlEvent::EndElement {
                 name: OwnedName { local_name, .. },
@@ -130,10 +223,41 @@ fn parse_event<R: Read>(parser: &mut EventReader<R>, events: &mut HashMap<u16, E
             Ok(XmlEvent::EndElement {
                 name: OwnedName { local_name, .. },
             }) if local_name.as_str() == "event" => {
-                events.insert(id, Event::new(name, id, elements.clone()));
-                elements.clear();
+                let id = match id {
+                    Some(id) => id,
+                    None => {
+                        diagnostics
+                            .push(Diagnostic::new(start, format!("event {} has no id", name)));
+                        return;
+                    }
+                };
+                if name.is_empty() {
+                    diagnostics.push(Diagnostic::new(start, format!("event {} has no name", id)));
+                }
+                if let Some(existing) = events.get(&id) {
+                    diagnostics.push(Diagnostic::new(
+                        start,
+                        format!("event {} reuses id {} of event {}", name, id, existing.name),
+                    ));
+                    return;
+                }
+                if let Some(existing) = events.values().find(|event| event.name == name) {
+                    diagnostics.push(Diagnostic::new(
+                        start,
+                        format!("event name {} is already used by id {}", name, existing.id),
+                    ));
+                }
+                events.insert(id, Event::new(name, id, elements));
+                positions.insert(
+                    id,
+                    EventPositions {
+                        event: start,
+                        params,
+                    },
+                );
                 return;
             }
+            Err(_) => return,
             _ => {}
         }
     }
@@ -142,7 +266,9 @@ fn parse_event<R: Read>(parser: &mut EventReader<R>, events: &mut HashMap<u16, E
 fn parse_parameter<R: Read>(
     parser: &mut EventReader<R>,
     parameters: &mut HashMap<String, Paramter>,
+    diagnostics: &mut Vec<Diagnostic>,
 ) {
+    let start = parser.position();
     let mut data = String::new();
 
     let mut name = String::new();
@@ -152,7 +278,7 @@ fn parse_parameter<R: Read>(
     let mut related_name = String::new();
 
     loop {
-        let event = &parser.next();
+        let event = parser.next();
 
         match event {
             Ok(XmlEvent::Characters(d)) => {
@@ -183,42 +309,179 @@ fn parse_parameter<R: Read>(
                 name: OwnedName { local_name, .. },
                 namespace: _,
                 attributes,
-            }) if local_name.as_str() == "enum" => {
-                enumeration.insert(
-                    attributes[1].value.parse().unwrap(),
-                    attributes[0].value.to_string(),
-                );
-            }
+            }) if local_name.as_str() == "enum" => match parse_enum(&attributes) {
+                Ok((value, enum_name)) => {
+                    if let Some(existing) = enumeration.get(&value) {
+                        diagnostics.push(Diagnostic::new(
+                            parser.position(),
+                            format!(
+                                "parameter {} enum value {} is used by {} and {}",
+                                name, value, existing, enum_name
+                            ),
+                        ));
+                    }
+                    enumeration.insert(value, enum_name);
+                }
+                Err(message) => diagnostics.push(Diagnostic::new(
+                    parser.position(),
+                    format!("parameter {} has malformed enum: {}", name, message),
+                )),
+            },
             Ok(XmlEvent::EndElement {
                 name: OwnedName { local_name, .. },
             }) if local_name.as_str() == "parametertype" => {
+                if parameters.contains_key(&name) {
+                    diagnostics.push(Diagnostic::new(
+                        start,
+                        format!("parameter {} is defined more than once", name),
+                    ));
+                }
                 parameters.insert(
                     name.to_string(),
                     Paramter {
                         name,
                         param_type,
                         number_of_bytes,
-                        enumeration: enumeration.clone(),
+                        enumeration,
                         related_name,
                     },
                 );
-                enumeration.clear();
                 return;
             }
+            Err(_) => return,
             _ => {}
         }
     }
 }
 
+fn parse_enum(attributes: &[OwnedAttribute]) -> Result<(u8, String), String> {
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|attribute| attribute.name.local_name == name)
+            .map(|attribute| attribute.value.to_string())
+    };
+    let enum_name = attribute("name").ok_or("missing name attribute")?;
+    let value = attribute("value").ok_or("missing value attribute")?;
+    match value.trim().parse() {
+        Ok(value) => Ok((value, enum_name)),
+        Err(_) => Err(format!(
+            "value '{}' of {} is not in 0..=255",
+            value, enum_name
+        )),
+    }
+}
+
 fn fill_events_with_paramters(
     events: &mut HashMap<u16, Event>,
+    positions: &HashMap<u16, EventPositions>,
     parameters: HashMap<String, Paramter>,
+    diagnostics: &mut Vec<Diagnostic>,
 ) {
-    for (_, event) in events {
-        for elemnt in &event.elements {
-            event
-                .parameters
-                .push(parameters.get(elemnt).unwrap().clone());
+    for (id, event) in events {
+        let event_positions = &positions[id];
+        for (index, elemnt) in event.elements.iter().enumerate() {
+            let position = event_positions.params[index];
+            let parameter = match parameters.get(elemnt) {
+                Some(parameter) => parameter,
+                None => {
+                    diagnostics.push(Diagnostic::new(
+                        position,
+                        format!(
+                            "event {} references undefined parameter {}",
+                            event.name, elemnt
+                        ),
+                    ));
+                    continue;
+                }
+            };
+            if parameter.number_of_bytes == -1
+                && !event.elements[..index].contains(&parameter.related_name)
+            {
+                let message = if parameters.contains_key(&parameter.related_name) {
+                    format!(
+                        "event {} has length parameter {} after {}",
+                        event.name, parameter.related_name, elemnt
+                    )
+                } else {
+                    format!(
+                        "parameter {} uses undefined length parameter {}",
+                        elemnt, parameter.related_name
+                    )
+                };
+                diagnostics.push(Diagnostic::new(position, message));
+            }
+            event.parameters.push(parameter.clone());
         }
+        if event.elements.is_empty() {
+            diagnostics.push(Diagnostic::new(
+                event_positions.event,
+                format!("event {} has no parameters", event.name),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(xml: &str) -> Vec<String> {
+        match read_schema(xml.as_bytes()) {
+            Ok(_) => Vec::new(),
+            Err(diagnostics) => diagnostics.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_read_schema() {
+        let xml = r#"<spec>
+<parametertype><name>LEN</name><type>UINT</type><numberofbytes>1</numberofbytes></parametertype>
+<parametertype><name>DIR</name><type>ENUM</type><numberofbytes>1</numberofbytes>
+<enum value="1" name="SENT"/><enum name="RECEIVED" value="0"/></parametertype>
+<parametertype><name>MSG</name><type>BINARY</type><numberofbytes>LEN</numberofbytes></parametertype>
+<event><name>EV</name><id>7</id><param>DIR</param><param>LEN</param><param>MSG</param></event>
+</spec>"#;
+
+        let events = read_schema(xml.as_bytes()).unwrap();
+        let event = &events[&7];
+
+        assert_eq!(event.parameters.len(), 3);
+        assert_eq!(event.parameters[0].enumeration[&1], "SENT");
+        assert_eq!(event.parameters[0].enumeration[&0], "RECEIVED");
+        assert_eq!(event.parameters[2].related_name, "LEN");
+    }
+
+    #[test]
+    fn test_read_schema_diagnostics() {
+        let xml = r#"<spec>
+<parametertype><name>LEN</name><type>UINT</type><numberofbytes>1</numberofbytes></parametertype>
+<parametertype><name>DIR</name><type>ENUM</type><numberofbytes>1</numberofbytes>
+<enum name="SENT"/><enum name="BIG" value="300"/></parametertype>
+<parametertype><name>MSG</name><type>BINARY</type><numberofbytes>LEN</numberofbytes></parametertype>
+<event><name>EV</name><id>7</id><param>MSG</param><param>LEN</param><param>NOPE</param></event>
+<event><name>EV2</name><id>7</id><param>LEN</param></event>
+<event><name>EV3</name><param>LEN</param></event>
+</spec>"#;
+
+        assert_eq!(
+            messages(xml),
+            vec![
+                "4:1: parameter DIR has malformed enum: missing value attribute",
+                "4:20: parameter DIR has malformed enum: value '300' of BIG is not in 0..=255",
+                "6:33: event EV has length parameter LEN after MSG",
+                "6:69: event EV references undefined parameter NOPE",
+                "7:1: event EV2 reuses id 7 of event EV",
+                "8:1: event EV3 has no id",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_schema_syntax_error() {
+        let diagnostics = messages("<spec><event></spec>");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].starts_with("1:"));
     }
 }